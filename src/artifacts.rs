@@ -0,0 +1,219 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+const MANIFEST_FILE: &str = "manifest.json";
+const RUN_LOG_FILE: &str = "run.log";
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ArtifactKind {
+    Screenshot,
+    Data,
+    Log,
+    Poster,
+}
+
+impl ArtifactKind {
+    fn dir_name(self) -> &'static str {
+        match self {
+            ArtifactKind::Screenshot => "screenshots",
+            ArtifactKind::Data => "data",
+            ArtifactKind::Log => "logs",
+            ArtifactKind::Poster => "posters",
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Artifact {
+    pub kind: ArtifactKind,
+    /// Path relative to the run directory, or absolute if written elsewhere.
+    pub path: PathBuf,
+    pub step: String,
+    pub created_at: DateTime<Local>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Manifest {
+    pub scraper: String,
+    pub started_at: DateTime<Local>,
+    pub finished_at: Option<DateTime<Local>>,
+    pub status: Option<String>,
+    pub artifacts: Vec<Artifact>,
+}
+
+/// The `runs/<timestamp>-<scraper>/` directory for one run, with its manifest.
+pub struct RunArtifacts {
+    root: PathBuf,
+    manifest: Mutex<Manifest>,
+}
+
+impl RunArtifacts {
+    pub fn create(base: &Path, scraper: &str) -> Result<Self> {
+        let started_at = Local::now();
+        let stamp = started_at.format("%Y%m%d-%H%M%S");
+        let mut root = base.join(format!("{}-{}", stamp, scraper));
+        let mut suffix = 1;
+        while root.exists() {
+            suffix += 1;
+            root = base.join(format!("{}-{}-{}", stamp, scraper, suffix));
+        }
+
+        for kind in [
+            ArtifactKind::Screenshot,
+            ArtifactKind::Data,
+            ArtifactKind::Log,
+            ArtifactKind::Poster,
+        ] {
+            std::fs::create_dir_all(root.join(kind.dir_name()))
+                .with_context(|| format!("Failed to create run directory {}", root.display()))?;
+        }
+
+        let artifacts = Self {
+            root,
+            manifest: Mutex::new(Manifest {
+                scraper: scraper.to_string(),
+                started_at,
+                finished_at: None,
+                status: None,
+                artifacts: Vec::new(),
+            }),
+        };
+        artifacts.record(
+            ArtifactKind::Log,
+            &artifacts.path(ArtifactKind::Log, RUN_LOG_FILE),
+            "run",
+        )?;
+        Ok(artifacts)
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Where an artifact of `kind` named `name` belongs in this run.
+    pub fn path(&self, kind: ArtifactKind, name: &str) -> PathBuf {
+        self.root.join(kind.dir_name()).join(name)
+    }
+
+    /// Path for the next screenshot of `step`, numbered so files sort in
+    /// the order they were taken.
+    pub fn screenshot_path(&self, step: &str) -> PathBuf {
+        let count = self
+            .lock()
+            .artifacts
+            .iter()
+            .filter(|a| matches!(a.kind, ArtifactKind::Screenshot))
+            .count();
+        self.path(
+            ArtifactKind::Screenshot,
+            &format!("{:03}-{}.png", count + 1, step),
+        )
+    }
+
+    /// Adds a written file to the manifest.
+    pub fn record(&self, kind: ArtifactKind, path: &Path, step: &str) -> Result<()> {
+        let mut manifest = self.lock();
+        manifest.artifacts.push(Artifact {
+            kind,
+            path: path.strip_prefix(&self.root).unwrap_or(path).to_path_buf(),
+            step: step.to_string(),
+            created_at: Local::now(),
+        });
+        self.write_manifest(&manifest)
+    }
+
+    /// Appends a timestamped line to the run log.
+    pub fn log(&self, message: &str) -> Result<()> {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.path(ArtifactKind::Log, RUN_LOG_FILE))?;
+        writeln!(file, "{} {}", Local::now().to_rfc3339(), message)?;
+        Ok(())
+    }
+
+    pub fn finish(&self, status: &str) -> Result<()> {
+        let mut manifest = self.lock();
+        manifest.finished_at = Some(Local::now());
+        manifest.status = Some(status.to_string());
+        self.write_manifest(&manifest)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Manifest> {
+        self.manifest.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn write_manifest(&self, manifest: &Manifest) -> Result<()> {
+        let json = serde_json::to_string_pretty(manifest)?;
+        std::fs::write(self.root.join(MANIFEST_FILE), json)?;
+        Ok(())
+    }
+}
+
+/// Which old runs to delete when a new one starts.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct RetentionPolicy {
+    /// Keep at most this many runs.
+    pub keep_last: usize,
+    /// Also delete runs older than this many days.
+    pub max_age_days: Option<u64>,
+}
+
+impl Default for RetentionPolicy {
+    fn default() -> Self {
+        Self {
+            keep_last: 20,
+            max_age_days: None,
+        }
+    }
+}
+
+/// Deletes run directories under `base` that fall outside `policy`. Only
+/// directories containing a manifest are considered runs, and the newest run
+/// is never deleted.
+pub fn prune_runs(base: &Path, policy: &RetentionPolicy) -> Result<Vec<PathBuf>> {
+    if !base.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut runs = Vec::new();
+    for entry in std::fs::read_dir(base)? {
+        let path = entry?.path();
+        let manifest_path = path.join(MANIFEST_FILE);
+        if !manifest_path.is_file() {
+            continue;
+        }
+        let started_at = std::fs::read_to_string(&manifest_path)
+            .ok()
+            .and_then(|json| serde_json::from_str::<Manifest>(&json).ok())
+            .map(|manifest| manifest.started_at);
+        runs.push((path, started_at));
+    }
+
+    // Newest first; runs with unreadable manifests sort last.
+    runs.sort_by_key(|run| std::cmp::Reverse(run.1));
+
+    let cutoff = policy
+        .max_age_days
+        .map(|days| Local::now() - chrono::Duration::days(days as i64));
+
+    let mut pruned = Vec::new();
+    for (index, (path, started_at)) in runs.into_iter().enumerate() {
+        let too_many = index >= policy.keep_last.max(1);
+        let too_old = matches!((cutoff, started_at), (Some(cutoff), Some(t)) if t < cutoff);
+        if too_many || (index > 0 && too_old) {
+            std::fs::remove_dir_all(&path)
+                .with_context(|| format!("Failed to remove old run {}", path.display()))?;
+            pruned.push(path);
+        }
+    }
+
+    Ok(pruned)
+}
@@ -0,0 +1,162 @@
+use crate::config::{BrowserProfile, WindowSize};
+use crate::output::OutputFormat;
+use crate::retry::RetryPolicy;
+use crate::scraper::Scraper;
+use anyhow::Result;
+use clap::{Args, FromArgMatches};
+use std::path::PathBuf;
+
+pub enum Invocation {
+    /// Run the scraper with this name.
+    Run(String),
+    ListScrapers,
+    /// Delete the saved browser session of one scraper, or of all.
+    ClearSession(Option<String>),
+    /// No subcommand given; fall back to the interactive menu.
+    Interactive,
+}
+
+/// Parses the command line, with one subcommand per registered scraper.
+pub fn parse(registry: &[Box<dyn Scraper>]) -> Result<(GlobalArgs, Invocation)> {
+    let mut command = clap::Command::new(env!("CARGO_PKG_NAME"))
+        .version(env!("CARGO_PKG_VERSION"))
+        .about("Browser automation for job applications and movie listings")
+        .after_help(
+            "Without a subcommand, an interactive menu is shown.\n\n\
+             Exit codes: 1 other error, 2 invalid configuration, 10 WebDriver connection, \
+             11 timeout, 12 element not found, 13 script error, 14 unexpected page, \
+             15 authentication required, 16 rate limited, \
+             17 manual action required (e.g. two-step verification)",
+        )
+        .subcommand(clap::Command::new("list-scrapers").about("List the available scrapers"))
+        .subcommand(
+            clap::Command::new("session")
+                .about("Manage the browser sessions saved between runs")
+                .subcommand_required(true)
+                .subcommand(
+                    clap::Command::new("clear")
+                        .about("Delete saved cookies and localStorage")
+                        .arg(clap::Arg::new("scraper").help(
+                            "Only clear this scraper's session (default: all, with the key)",
+                        )),
+                ),
+        );
+    for scraper in registry {
+        command =
+            command.subcommand(clap::Command::new(scraper.name()).about(scraper.description()));
+    }
+
+    let matches = GlobalArgs::augment_args(command).get_matches();
+    let global = GlobalArgs::from_arg_matches(&matches)?;
+    let invocation = match matches.subcommand() {
+        Some(("list-scrapers", _)) => Invocation::ListScrapers,
+        Some(("session", session)) => match session.subcommand() {
+            Some(("clear", clear)) => {
+                Invocation::ClearSession(clear.get_one::<String>("scraper").cloned())
+            }
+            _ => unreachable!("session requires a subcommand"),
+        },
+        Some((name, _)) => Invocation::Run(name.to_string()),
+        None => Invocation::Interactive,
+    };
+    Ok((global, invocation))
+}
+
+#[derive(Debug, Args)]
+pub struct GlobalArgs {
+    /// Path to the TOML config file (defaults to $SCRAPER_CONFIG or ./scraper.toml)
+    #[arg(long, global = true)]
+    pub config: Option<PathBuf>,
+
+    /// WebDriver endpoint to connect to
+    #[arg(
+        long,
+        global = true,
+        env = "WEBDRIVER_URL",
+        default_value = "http://localhost:9515"
+    )]
+    pub webdriver_url: String,
+
+    /// Number of attempts when connecting to the WebDriver (overrides `[retry.steps.connect]`)
+    #[arg(long, global = true)]
+    pub connect_retries: Option<u32>,
+
+    /// Seconds to wait between connection attempts (overrides `[retry.steps.connect]`)
+    #[arg(long, global = true)]
+    pub connect_delay: Option<u64>,
+
+    /// Where the scraper writes its results (defaults to the run's data directory)
+    #[arg(short, long, global = true)]
+    pub output: Option<PathBuf>,
+
+    /// Format of the results (defaults to `[output] format`)
+    #[arg(long, global = true, value_enum)]
+    pub format: Option<OutputFormat>,
+
+    /// Directory run artifacts are written to (defaults to `[runs] dir`)
+    #[arg(long, global = true)]
+    pub runs_dir: Option<PathBuf>,
+
+    /// Print more detail (screenshot paths, URLs, page titles)
+    #[arg(short, long, global = true, action = clap::ArgAction::Count)]
+    pub verbose: u8,
+
+    #[command(flatten)]
+    pub browser: BrowserArgs,
+}
+
+impl GlobalArgs {
+    /// Applies `--connect-retries` and `--connect-delay` to the connect policy.
+    pub fn apply_to_connect_policy(&self, policy: &mut RetryPolicy) {
+        if let Some(retries) = self.connect_retries {
+            policy.max_attempts = retries;
+        }
+        if let Some(delay) = self.connect_delay {
+            policy.initial_delay_ms = delay * 1000;
+        }
+    }
+}
+
+/// Command-line overrides for the `[browser]` config section.
+#[derive(Debug, Args)]
+pub struct BrowserArgs {
+    /// Browser executable to launch
+    #[arg(long, global = true)]
+    pub chrome_binary: Option<PathBuf>,
+
+    /// Run the browser headless
+    #[arg(long, global = true, conflicts_with = "headed")]
+    pub headless: bool,
+
+    /// Run the browser with a visible window
+    #[arg(long, global = true)]
+    pub headed: bool,
+
+    /// Browser window size as WIDTHxHEIGHT
+    #[arg(long, global = true)]
+    pub window_size: Option<WindowSize>,
+
+    /// Chrome user data directory
+    #[arg(long, global = true)]
+    pub user_data_dir: Option<PathBuf>,
+}
+
+impl BrowserArgs {
+    pub fn apply_to(&self, profile: &mut BrowserProfile) {
+        if let Some(binary) = &self.chrome_binary {
+            profile.binary = Some(binary.clone());
+        }
+        if self.headless {
+            profile.headless = true;
+        }
+        if self.headed {
+            profile.headless = false;
+        }
+        if let Some(size) = self.window_size {
+            profile.window_size = Some(size);
+        }
+        if let Some(dir) = &self.user_data_dir {
+            profile.user_data_dir = Some(dir.clone());
+        }
+    }
+}
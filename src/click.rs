@@ -0,0 +1,261 @@
+use crate::common::BrowserSession;
+use crate::debug_println;
+use crate::error::{Result, ScrapeError};
+use crate::selectors::SelectorChain;
+use crate::step::{StepOutcome, STEP_PRELUDE};
+use fantoccini::actions::{InputSource, MouseActions, PointerAction, MOUSE_BUTTON_LEFT};
+use fantoccini::elements::Element;
+use fantoccini::error::CmdError;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::fmt;
+
+/// Key of an element reference in a WebDriver script result.
+const ELEMENT_KEY: &str = "element-6066-11e4-a52e-4f735466cecf";
+
+/// Finds the first visible element matching the target, trying the
+/// selectors of a chain in order, scrolls it to the middle of the viewport
+/// and checks nothing else covers its centre. Returns the element alongside
+/// a step outcome.
+const RESOLVE_SCRIPT: &str = r#"
+async function resolve(chain, role, text) {
+    const implicitRoles = {
+        button: 'button, input[type="button"], input[type="submit"], input[type="reset"]',
+        link: 'a[href]',
+        checkbox: 'input[type="checkbox"]',
+        radio: 'input[type="radio"]',
+        textbox: 'input:not([type]), input[type="text"], input[type="email"], textarea',
+        option: 'option',
+        listitem: 'li',
+    };
+    const clean = value => value ? value.replace(/\s+/g, ' ').trim() : '';
+    const describe = el => el.tagName.toLowerCase()
+        + (el.id ? `#${el.id}` : '')
+        + (typeof el.className === 'string' && el.className.trim() ? `.${el.className.trim().split(/\s+/).join('.')}` : '');
+    const nameOf = el => {
+        const labelledBy = el.getAttribute('aria-labelledby');
+        const labels = labelledBy
+            ? labelledBy.split(/\s+/).map(id => document.getElementById(id)).filter(Boolean).map(l => l.textContent).join(' ')
+            : '';
+        return clean([el.getAttribute('aria-label'), labels, el.innerText || el.textContent, el.value, el.title, el.alt]
+            .filter(value => typeof value === 'string').join(' ')).toLowerCase();
+    };
+    const isVisible = el => {
+        const rect = el.getBoundingClientRect();
+        const style = window.getComputedStyle(el);
+        return rect.width > 0 && rect.height > 0 && style.visibility !== 'hidden'
+            && style.display !== 'none' && parseFloat(style.opacity) > 0;
+    };
+
+    const queries = chain || [`[role="${role}"]` + (implicitRoles[role] ? `, ${implicitRoles[role]}` : '')];
+    let matching = 0;
+    let el = null;
+    for (const query of queries) {
+        let candidates;
+        try {
+            candidates = Array.from(document.querySelectorAll(query));
+        } catch (e) {
+            return { outcome: failed('invalid_selector', `${query}: ${e.message}`), element: null };
+        }
+        if (text) {
+            const wanted = clean(text).toLowerCase();
+            candidates = candidates.filter(candidate => nameOf(candidate).includes(wanted));
+        }
+        matching += candidates.length;
+        el = candidates.find(isVisible);
+        if (el) break;
+    }
+    if (!el) {
+        const detail = matching ? `${matching} matching, none visible` : 'no element matches';
+        return { outcome: failed('not_found', detail), element: null };
+    }
+
+    el.scrollIntoView({ block: 'center', inline: 'center' });
+    await new Promise(resolve => requestAnimationFrame(() => resolve()));
+    const rect = el.getBoundingClientRect();
+    const top = document.elementFromPoint(rect.left + rect.width / 2, rect.top + rect.height / 2);
+    if (!top || !(top === el || el.contains(top))) {
+        return {
+            outcome: failed('unexpected_page', `${describe(el)} is covered by ${top ? describe(top) : 'nothing clickable'}`),
+            element: null,
+        };
+    }
+    return { outcome: ok('found', describe(el)), element: el };
+}
+return resolve(arguments[0], arguments[1], arguments[2]);
+"#;
+
+/// An element to click, found by selector chain or ARIA role and optionally
+/// narrowed by its text.
+#[derive(Debug, Clone)]
+pub struct ClickTarget {
+    chain: Option<SelectorChain>,
+    role: Option<String>,
+    text: Option<String>,
+    /// Viewport point clicked when no usable element is found. Only set
+    /// through [`ClickTarget::or_at_point`].
+    point: Option<(i64, i64)>,
+}
+
+/// How a [`ClickTarget`] ended up being clicked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClickMethod {
+    /// WebDriver's element click.
+    Native,
+    /// A pointer press and release over the element, for elements the
+    /// native click refuses.
+    Pointer,
+    /// A pointer press and release at the opted-in viewport point.
+    Point(i64, i64),
+}
+
+impl ClickTarget {
+    /// The first visible element matching `chain`, trying its selectors
+    /// in order.
+    pub fn css(chain: &SelectorChain) -> Self {
+        Self {
+            chain: Some(chain.clone()),
+            role: None,
+            text: None,
+            point: None,
+        }
+    }
+
+    /// Elements with this ARIA role, set explicitly or implied by the tag,
+    /// e.g. `button` also matches `<button>` and submit inputs.
+    pub fn role(role: &str) -> Self {
+        Self {
+            chain: None,
+            role: Some(role.to_string()),
+            text: None,
+            point: None,
+        }
+    }
+
+    /// Only elements whose accessible name or text contains `text`,
+    /// ignoring case and spacing.
+    pub fn with_text(mut self, text: &str) -> Self {
+        self.text = Some(text.to_string());
+        self
+    }
+
+    /// Clicks at this viewport point if the element cannot be found or is
+    /// covered. Whatever is at the point gets the click, so this is for
+    /// layouts known to be fixed.
+    pub fn or_at_point(mut self, x: i64, y: i64) -> Self {
+        self.point = Some((x, y));
+        self
+    }
+
+    /// Clicks the element, falling back to a pointer click when the native
+    /// one is refused and to the opted-in point when the element is not
+    /// usable at all.
+    pub async fn click(&self, session: &BrowserSession) -> Result<ClickMethod> {
+        let element = match self.resolve(session).await {
+            Ok(element) => element,
+            Err(e @ (ScrapeError::SelectorMissing(_) | ScrapeError::UnexpectedPage(_))) => {
+                let Some((x, y)) = self.point else {
+                    return Err(e);
+                };
+                println!("{}; clicking at ({}, {}) instead", e, x, y);
+                let actions = MouseActions::new("click".to_string())
+                    .then(PointerAction::MoveTo {
+                        duration: None,
+                        x,
+                        y,
+                    })
+                    .then(PointerAction::Down {
+                        button: MOUSE_BUTTON_LEFT,
+                    })
+                    .then(PointerAction::Up {
+                        button: MOUSE_BUTTON_LEFT,
+                    });
+                session.client().perform_actions(actions).await?;
+                return Ok(ClickMethod::Point(x, y));
+            }
+            Err(e) => return Err(e),
+        };
+
+        match element.click().await {
+            Ok(()) => return Ok(ClickMethod::Native),
+            Err(e) if refused(&e) => debug_println!("Native click on {} refused: {}", self, e),
+            Err(e) => return Err(e.into()),
+        }
+        let actions = MouseActions::new("click".to_string())
+            .then(PointerAction::MoveToElement {
+                element,
+                duration: None,
+                x: 0,
+                y: 0,
+            })
+            .then(PointerAction::Down {
+                button: MOUSE_BUTTON_LEFT,
+            })
+            .then(PointerAction::Up {
+                button: MOUSE_BUTTON_LEFT,
+            });
+        match session.client().perform_actions(actions).await {
+            Ok(()) => Ok(ClickMethod::Pointer),
+            Err(e) if refused(&e) => Err(ScrapeError::UnexpectedPage(format!(
+                "{} cannot be clicked: {}",
+                self, e
+            ))),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn resolve(&self, session: &BrowserSession) -> Result<Element> {
+        #[derive(Deserialize)]
+        struct Resolved {
+            outcome: StepOutcome,
+            element: Option<Value>,
+        }
+
+        let step = format!("click {}", self);
+        let script = format!("{}\n{}", STEP_PRELUDE, RESOLVE_SCRIPT);
+        let resolved: Resolved = session
+            .execute_with_args(
+                &script,
+                vec![json!(self.chain), json!(self.role), json!(self.text)],
+            )
+            .await?;
+        debug_println!("{}: {}", step, resolved.outcome);
+        resolved.outcome.into_result(&step)?;
+        let id = resolved
+            .element
+            .as_ref()
+            .and_then(|element| element.get(ELEMENT_KEY))
+            .and_then(Value::as_str)
+            .ok_or_else(|| ScrapeError::Script(format!("{}: no element returned", step)))?;
+        Ok(Element::from_element_id(
+            session.client().clone(),
+            id.to_string().into(),
+        ))
+    }
+}
+
+impl fmt::Display for ClickTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.chain, &self.role) {
+            (Some(chain), _) => write!(f, "{}", chain)?,
+            (None, Some(role)) => write!(f, "{}", role)?,
+            (None, None) => f.write_str("element")?,
+        }
+        if let Some(text) = &self.text {
+            write!(f, " \"{}\"", text)?;
+        }
+        Ok(())
+    }
+}
+
+/// Whether WebDriver declined the click because of the element's state, as
+/// opposed to the session failing.
+fn refused(error: &CmdError) -> bool {
+    matches!(
+        error,
+        CmdError::Standard(wd) if matches!(
+            wd.error(),
+            "element click intercepted" | "element not interactable" | "move target out of bounds"
+        )
+    )
+}
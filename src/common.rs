@@ -1,39 +1,386 @@
-use anyhow::{anyhow, Result};
-use fantoccini::{Client, ClientBuilder};
+use crate::artifacts::{ArtifactKind, RunArtifacts};
+use crate::click::{ClickMethod, ClickTarget};
+use crate::config::BrowserProfile;
+use crate::error::{Result, ScrapeError};
+use crate::retry::RetryPolicy;
+use crate::selectors::{SelectorChain, SELECTOR_JS};
+use crate::session_store::{SavedCookie, SavedSession};
+use crate::step::{StepOutcome, STEP_PRELUDE};
+use crate::wait::{Wait, WaitConfig};
+use cookie::time::OffsetDateTime;
+use cookie::SameSite;
+use fantoccini::cookies::Cookie;
+use fantoccini::elements::Element;
+use fantoccini::error::CmdError;
+use fantoccini::{Client, ClientBuilder, Locator};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
 use serde_json::Value;
-use std::time::Duration;
-
-pub async fn connect_with_retry(url: &str, retries: u32, delay: Duration) -> Result<Client> {
-    let mut caps = serde_json::map::Map::new();
-    let chrome_opts = serde_json::json!({
-        "args": ["--headless", "--disable-gpu"],
-        "binary": "/Applications/Google Chrome for Testing.app/Contents/MacOS/Google Chrome for Testing",  // Adjust this path
-    });
-    caps.insert("goog:chromeOptions".to_string(), chrome_opts);
-
-    let mut attempt = 0;
-    loop {
-        match ClientBuilder::native()
-            .capabilities(caps.clone())
-            .connect(url)
-            .await
-        {
-            Ok(client) => return Ok(client),
-            Err(e) => {
-                attempt += 1;
-                if attempt >= retries {
-                    return Err(e.into());
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, AtomicU8, Ordering};
+use std::sync::Arc;
+
+static VERBOSITY: AtomicU8 = AtomicU8::new(0);
+
+pub fn set_verbosity(level: u8) {
+    VERBOSITY.store(level, Ordering::Relaxed);
+}
+
+pub fn verbosity() -> u8 {
+    VERBOSITY.load(Ordering::Relaxed)
+}
+
+/// Like `println!`, but only printed when running with `--verbose`.
+#[macro_export]
+macro_rules! debug_println {
+    ($($arg:tt)*) => {
+        if $crate::common::verbosity() > 0 {
+            println!($($arg)*);
+        }
+    };
+}
+
+pub async fn connect_with_retry(
+    url: &str,
+    profile: &BrowserProfile,
+    policy: &RetryPolicy,
+) -> Result<Client> {
+    let caps = profile.capabilities();
+    policy
+        .run("connect", |_| {
+            let caps = caps.clone();
+            async move {
+                let client = ClientBuilder::native()
+                    .capabilities(caps)
+                    .connect(url)
+                    .await?;
+                Ok(client)
+            }
+        })
+        .await
+}
+
+/// Opens browser sessions that share the run's profile, artifacts and
+/// waits, for scrapers that drive several browsers at once.
+pub struct SessionFactory {
+    webdriver_url: String,
+    profile: BrowserProfile,
+    connect: RetryPolicy,
+    artifacts: Arc<RunArtifacts>,
+    waits: WaitConfig,
+}
+
+impl SessionFactory {
+    pub fn new(
+        webdriver_url: String,
+        profile: BrowserProfile,
+        connect: RetryPolicy,
+        artifacts: Arc<RunArtifacts>,
+        waits: WaitConfig,
+    ) -> Self {
+        Self {
+            webdriver_url,
+            profile,
+            connect,
+            artifacts,
+            waits,
+        }
+    }
+
+    pub async fn open(&self) -> Result<BrowserSession> {
+        let client = connect_with_retry(&self.webdriver_url, &self.profile, &self.connect).await?;
+        Ok(BrowserSession::new(
+            client,
+            self.artifacts.clone(),
+            self.waits,
+        ))
+    }
+
+    /// Chrome locks its user data directory, so a profile that sets one
+    /// can only back a single session.
+    pub fn supports_parallel(&self) -> bool {
+        self.profile.user_data_dir.is_none()
+    }
+}
+
+/// A WebDriver client plus the housekeeping every scraper needs around it.
+pub struct BrowserSession {
+    client: Client,
+    artifacts: Arc<RunArtifacts>,
+    waits: WaitConfig,
+}
+
+impl BrowserSession {
+    pub fn new(client: Client, artifacts: Arc<RunArtifacts>, waits: WaitConfig) -> Self {
+        Self {
+            client,
+            artifacts,
+            waits,
+        }
+    }
+
+    /// Starts a condition wait using the configured timeout and interval.
+    pub fn wait(&self) -> Wait<'_> {
+        Wait::new(self, self.waits)
+    }
+
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+
+    pub fn artifacts(&self) -> &RunArtifacts {
+        &self.artifacts
+    }
+
+    /// Runs `script` and deserializes its return value into `T`.
+    pub async fn execute<T: DeserializeOwned>(&self, script: &str) -> Result<T> {
+        self.execute_with_args(script, Vec::new()).await
+    }
+
+    /// Like [`execute`](Self::execute), exposing `args` to the script as `arguments`.
+    /// Scripts can use the selector chain helpers from [`SELECTOR_JS`].
+    pub async fn execute_with_args<T: DeserializeOwned>(
+        &self,
+        script: &str,
+        args: Vec<Value>,
+    ) -> Result<T> {
+        let script = format!("{}\n{}", SELECTOR_JS, script);
+        let result = self.client.execute(&script, args).await?;
+        serde_json::from_value(result)
+            .map_err(|e| ScrapeError::Script(format!("unexpected script result: {}", e)))
+    }
+
+    /// Runs a step script that reports back through the `ok`/`skipped`/`failed`
+    /// helpers from [`STEP_PRELUDE`], exposing `args` to it as `arguments`. A
+    /// `failed` outcome becomes a typed [`ScrapeError`].
+    pub async fn run_step_with_args(
+        &self,
+        step: &str,
+        script: &str,
+        args: Vec<Value>,
+    ) -> Result<StepOutcome> {
+        let script = format!("{}\n{}", STEP_PRELUDE, script);
+        let outcome: StepOutcome = match self.execute_with_args(&script, args).await {
+            Err(ScrapeError::Script(e)) => {
+                return Err(ScrapeError::Script(format!(
+                    "{}: script did not report a step outcome: {}",
+                    step, e
+                )))
+            }
+            result => result?,
+        };
+        debug_println!("{}: {}", step, outcome);
+        outcome.into_result(step)
+    }
+
+    /// The first element matching `chain`, trying its selectors in order.
+    pub async fn find(&self, chain: &SelectorChain) -> Result<Element> {
+        for selector in chain.iter() {
+            match self.client.find(Locator::Css(selector)).await {
+                Ok(element) => return Ok(element),
+                Err(CmdError::NoSuchElement(_)) => continue,
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Err(ScrapeError::SelectorMissing(chain.to_string()))
+    }
+
+    /// Clicks `target` as a user would; see [`ClickTarget::click`].
+    pub async fn click(&self, target: &ClickTarget) -> Result<ClickMethod> {
+        target.click(self).await
+    }
+
+    /// Saves a screenshot into the run's artifact directory, tagged with `step`.
+    pub async fn screenshot(&self, step: &str) -> Result<PathBuf> {
+        let path = self.artifacts.screenshot_path(step);
+        let screenshot = self.client.screenshot().await?;
+        std::fs::write(&path, &screenshot)?;
+        self.artifacts
+            .record(ArtifactKind::Screenshot, &path, step)?;
+        debug_println!("Screenshot saved as {}", path.display());
+        Ok(path)
+    }
+
+    /// Closes every window except the current one, e.g. ad popups.
+    pub async fn close_other_tabs(&self) -> Result<()> {
+        let handles = self.client.windows().await?;
+        let current_handle = self.client.window().await?;
+
+        for handle in handles {
+            if handle != current_handle {
+                self.client.switch_to_window(handle).await?;
+                self.client.close_window().await?;
+            }
+        }
+
+        self.client.switch_to_window(current_handle).await?;
+        Ok(())
+    }
+
+    pub async fn log_current_url(&self) -> Result<()> {
+        let current_url = self.client.current_url().await?;
+        debug_println!("Current URL: {}", current_url);
+        self.artifacts
+            .log(&format!("Current URL: {}", current_url))?;
+        Ok(())
+    }
+
+    pub async fn log_page_title(&self) -> Result<()> {
+        let title = self.client.title().await?;
+        debug_println!("Page title: {:?}", title);
+        self.artifacts.log(&format!("Page title: {:?}", title))?;
+        Ok(())
+    }
+
+    /// The cookies and localStorage of the current page's site, or `None`
+    /// when no site is open.
+    pub async fn export_state(&self) -> Result<Option<SavedSession>> {
+        #[derive(Deserialize)]
+        struct PageState {
+            origin: String,
+            storage: BTreeMap<String, String>,
+        }
+
+        let url = self.client.current_url().await?;
+        if !matches!(url.scheme(), "http" | "https") {
+            return Ok(None);
+        }
+        let page: PageState = self
+            .execute(
+                r#"
+                const storage = {};
+                for (let i = 0; i < localStorage.length; i++) {
+                    const key = localStorage.key(i);
+                    storage[key] = localStorage.getItem(key);
                 }
-                println!(
-                    "Connection attempt {} failed, retrying in {:?}...",
-                    attempt, delay
-                );
-                tokio::time::sleep(delay).await;
+                return { origin: window.location.origin, storage };
+                "#,
+            )
+            .await?;
+        let cookies = self
+            .client
+            .get_all_cookies()
+            .await?
+            .into_iter()
+            .map(|cookie| SavedCookie {
+                name: cookie.name().to_string(),
+                value: cookie.value().to_string(),
+                domain: cookie.domain().map(str::to_string),
+                path: cookie.path().map(str::to_string),
+                secure: cookie.secure().unwrap_or_default(),
+                http_only: cookie.http_only().unwrap_or_default(),
+                expires: cookie.expires_datetime().map(|at| at.unix_timestamp()),
+                same_site: cookie.same_site().map(|s| s.to_string()),
+            })
+            .collect();
+        Ok(Some(SavedSession {
+            saved_at: chrono::Local::now(),
+            origin: page.origin,
+            cookies,
+            local_storage: page.storage,
+        }))
+    }
+
+    /// Puts saved cookies and localStorage back. Both can only be set for
+    /// the site that is open, so this opens the origin's `robots.txt` first,
+    /// which is cheap and has no scripts of its own; the scraper navigates
+    /// on from there.
+    pub async fn restore_state(&self, saved: &SavedSession) -> Result<()> {
+        self.client
+            .goto(&format!("{}/robots.txt", saved.origin))
+            .await?;
+        for saved_cookie in &saved.cookies {
+            let mut cookie = Cookie::new(saved_cookie.name.clone(), saved_cookie.value.clone());
+            if let Some(domain) = &saved_cookie.domain {
+                cookie.set_domain(domain.clone());
+            }
+            if let Some(path) = &saved_cookie.path {
+                cookie.set_path(path.clone());
+            }
+            cookie.set_secure(saved_cookie.secure);
+            cookie.set_http_only(saved_cookie.http_only);
+            if let Some(expires) = saved_cookie.expires {
+                cookie.set_expires(OffsetDateTime::from_unix_timestamp(expires).ok());
+            }
+            cookie.set_same_site(match saved_cookie.same_site.as_deref() {
+                Some("Strict") => Some(SameSite::Strict),
+                Some("Lax") => Some(SameSite::Lax),
+                Some("None") => Some(SameSite::None),
+                _ => None,
+            });
+            // One cookie the browser refuses should not cost the others.
+            if let Err(e) = self.client.add_cookie(cookie).await {
+                debug_println!("Could not restore cookie {}: {}", saved_cookie.name, e);
             }
         }
+        self.execute_with_args::<Value>(
+            r#"
+            for (const [key, value] of Object.entries(arguments[0])) {
+                localStorage.setItem(key, value);
+            }
+            return null;
+            "#,
+            vec![serde_json::to_value(&saved.local_storage)?],
+        )
+        .await?;
+        Ok(())
+    }
+
+    pub async fn close(self) -> Result<()> {
+        self.client.close().await?;
+        Ok(())
     }
 }
 
-pub async fn execute_script(client: &Client, script: &str) -> Result<Value> {
-    client.execute(script, vec![]).await.map_err(|e| anyhow!("Failed to execute script: {:?}", e))
-}
\ No newline at end of file
+/// Replaces `path` with `bytes` through a temporary file in the same
+/// directory, so readers and concurrent writers only ever see a complete
+/// file. Creates the parent directory if needed.
+pub fn write_atomic(path: &Path, bytes: &[u8]) -> std::io::Result<()> {
+    write_through_tmp(path, bytes, std::fs::OpenOptions::new())
+}
+
+/// [`write_atomic`], with the file created with mode 0600 on Unix.
+pub fn write_atomic_private(path: &Path, bytes: &[u8]) -> std::io::Result<()> {
+    let mut options = std::fs::OpenOptions::new();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    write_through_tmp(path, bytes, options)
+}
+
+fn write_through_tmp(
+    path: &Path,
+    bytes: &[u8],
+    mut options: std::fs::OpenOptions,
+) -> std::io::Result<()> {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)?;
+    }
+    // Unique per process and call, so two writers never share a temp file.
+    let mut tmp_name = path.file_name().unwrap_or_default().to_os_string();
+    tmp_name.push(format!(
+        ".{}.{}.tmp",
+        std::process::id(),
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    let tmp = path.with_file_name(tmp_name);
+    let written = options
+        .write(true)
+        .create_new(true)
+        .open(&tmp)
+        .and_then(|mut file| {
+            file.write_all(bytes)?;
+            file.sync_all()
+        })
+        .and_then(|()| std::fs::rename(&tmp, path));
+    if written.is_err() {
+        let _ = std::fs::remove_file(&tmp);
+    }
+    written
+}
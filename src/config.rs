@@ -0,0 +1,191 @@
+use crate::artifacts::RetentionPolicy;
+use crate::output::OutputConfig;
+use crate::retry::RetryConfig;
+use crate::session_store::SessionConfig;
+use crate::wait::WaitConfig;
+use anyhow::{anyhow, Context, Result};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+pub const DEFAULT_CONFIG_PATH: &str = "scraper.toml";
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub browser: BrowserProfile,
+    pub runs: RunsConfig,
+    pub waits: WaitConfig,
+    pub retry: RetryConfig,
+    pub output: OutputConfig,
+    pub session: SessionConfig,
+    /// Per-scraper settings, keyed by scraper name.
+    pub scrapers: HashMap<String, toml::Table>,
+}
+
+impl Config {
+    /// Loads the config from `path`, or from `$SCRAPER_CONFIG` / `scraper.toml`
+    /// when no path is given. A missing default file yields the built-in defaults.
+    pub fn load(path: Option<&Path>) -> Result<Self> {
+        let env_path = std::env::var_os("SCRAPER_CONFIG").map(PathBuf::from);
+        let explicit = path.map(Path::to_path_buf).or(env_path);
+
+        let mut config = match explicit {
+            Some(path) => Self::from_file(&path)?,
+            None if Path::new(DEFAULT_CONFIG_PATH).exists() => {
+                Self::from_file(Path::new(DEFAULT_CONFIG_PATH))?
+            }
+            None => Self::default(),
+        };
+
+        config.browser.apply_env_overrides()?;
+        Ok(config)
+    }
+
+    fn from_file(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read config file {}", path.display()))?;
+        toml::from_str(&contents)
+            .with_context(|| format!("Failed to parse config file {}", path.display()))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct RunsConfig {
+    /// Parent directory of the per-run artifact directories.
+    pub dir: PathBuf,
+    #[serde(flatten)]
+    pub retention: RetentionPolicy,
+}
+
+impl Default for RunsConfig {
+    fn default() -> Self {
+        Self {
+            dir: PathBuf::from("runs"),
+            retention: RetentionPolicy::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub struct WindowSize {
+    pub width: u32,
+    pub height: u32,
+}
+
+impl std::str::FromStr for WindowSize {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (width, height) = s
+            .split_once(['x', ','])
+            .ok_or_else(|| anyhow!("Invalid window size '{}', expected WIDTHxHEIGHT", s))?;
+        Ok(Self {
+            width: width.trim().parse()?,
+            height: height.trim().parse()?,
+        })
+    }
+}
+
+/// How to launch the browser behind the WebDriver session.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct BrowserProfile {
+    /// Browser executable. When unset the driver picks its default Chrome.
+    pub binary: Option<PathBuf>,
+    pub args: Vec<String>,
+    pub window_size: Option<WindowSize>,
+    pub headless: bool,
+    pub user_data_dir: Option<PathBuf>,
+    /// Extra top-level WebDriver capabilities merged over the generated ones.
+    pub capabilities: Map<String, Value>,
+}
+
+impl Default for BrowserProfile {
+    fn default() -> Self {
+        Self {
+            binary: None,
+            args: vec!["--disable-gpu".to_string()],
+            window_size: Some(WindowSize {
+                width: 1920,
+                height: 1080,
+            }),
+            headless: true,
+            user_data_dir: None,
+            capabilities: Map::new(),
+        }
+    }
+}
+
+impl BrowserProfile {
+    fn apply_env_overrides(&mut self) -> Result<()> {
+        if let Some(binary) = std::env::var_os("SCRAPER_CHROME_BINARY") {
+            self.binary = Some(PathBuf::from(binary));
+        }
+        if let Ok(args) = std::env::var("SCRAPER_CHROME_ARGS") {
+            self.args = args.split_whitespace().map(str::to_string).collect();
+        }
+        if let Ok(headless) = std::env::var("SCRAPER_HEADLESS") {
+            self.headless = parse_bool(&headless)
+                .ok_or_else(|| anyhow!("Invalid SCRAPER_HEADLESS value '{}'", headless))?;
+        }
+        if let Ok(size) = std::env::var("SCRAPER_WINDOW_SIZE") {
+            self.window_size = Some(size.parse()?);
+        }
+        if let Some(dir) = std::env::var_os("SCRAPER_USER_DATA_DIR") {
+            self.user_data_dir = Some(PathBuf::from(dir));
+        }
+        Ok(())
+    }
+
+    /// Builds the WebDriver capabilities, including `goog:chromeOptions`.
+    pub fn capabilities(&self) -> Map<String, Value> {
+        let mut args = self.args.clone();
+        if self.headless {
+            args.push("--headless=new".to_string());
+        }
+        if let Some(size) = self.window_size {
+            args.push(format!("--window-size={},{}", size.width, size.height));
+        }
+        if let Some(dir) = &self.user_data_dir {
+            args.push(format!("--user-data-dir={}", dir.display()));
+        }
+
+        let mut chrome_opts = Map::new();
+        chrome_opts.insert("args".to_string(), Value::from(args));
+        if let Some(binary) = &self.binary {
+            chrome_opts.insert(
+                "binary".to_string(),
+                Value::from(binary.display().to_string()),
+            );
+        }
+
+        let mut caps = Map::new();
+        caps.insert("goog:chromeOptions".to_string(), Value::Object(chrome_opts));
+
+        for (key, value) in &self.capabilities {
+            match (caps.get_mut(key), value) {
+                (Some(Value::Object(existing)), Value::Object(extra)) => {
+                    for (k, v) in extra {
+                        existing.insert(k.clone(), v.clone());
+                    }
+                }
+                _ => {
+                    caps.insert(key.clone(), value.clone());
+                }
+            }
+        }
+
+        caps
+    }
+}
+
+fn parse_bool(value: &str) -> Option<bool> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "1" | "true" | "yes" | "on" => Some(true),
+        "0" | "false" | "no" | "off" => Some(false),
+        _ => None,
+    }
+}
@@ -0,0 +1,73 @@
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use std::fmt;
+use std::path::Path;
+
+/// A site login. The password is kept out of `Debug` output so it cannot end
+/// up in logs.
+#[derive(Clone, Deserialize)]
+pub struct Credentials {
+    pub username: String,
+    pub password: String,
+}
+
+impl fmt::Debug for Credentials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Credentials")
+            .field("username", &self.username)
+            .field("password", &"<redacted>")
+            .finish()
+    }
+}
+
+impl Credentials {
+    /// Reads `<PREFIX>_USERNAME` and `<PREFIX>_PASSWORD` from the
+    /// environment, or failing that the TOML file at `file` with `username`
+    /// and `password` keys. `None` if neither is set up.
+    pub fn load(env_prefix: &str, file: &Path) -> Result<Option<Self>> {
+        let username = std::env::var(format!("{}_USERNAME", env_prefix)).ok();
+        let password = std::env::var(format!("{}_PASSWORD", env_prefix)).ok();
+        match (username, password) {
+            (Some(username), Some(password)) => {
+                return Ok(Some(Self { username, password }));
+            }
+            (Some(_), None) | (None, Some(_)) => bail!(
+                "Set both {0}_USERNAME and {0}_PASSWORD, or neither",
+                env_prefix
+            ),
+            (None, None) => {}
+        }
+
+        if !file.exists() {
+            return Ok(None);
+        }
+        check_permissions(file)?;
+        let text = std::fs::read_to_string(file)
+            .with_context(|| format!("Failed to read credentials file {}", file.display()))?;
+        let credentials = toml::from_str(&text)
+            .with_context(|| format!("Failed to parse credentials file {}", file.display()))?;
+        Ok(Some(credentials))
+    }
+}
+
+/// Refuses credentials files other users can read or write.
+#[cfg(unix)]
+fn check_permissions(file: &Path) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let mode = std::fs::metadata(file)?.permissions().mode() & 0o777;
+    if mode & 0o077 != 0 {
+        bail!(
+            "Credentials file {} is accessible by other users (mode {:o}); run `chmod 600 {}`",
+            file.display(),
+            mode,
+            file.display()
+        );
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn check_permissions(_file: &Path) -> Result<()> {
+    Ok(())
+}
@@ -0,0 +1,124 @@
+use fantoccini::error::{CmdError, NewSessionError};
+use serde::Deserialize;
+
+pub type Result<T, E = ScrapeError> = std::result::Result<T, E>;
+
+/// Why a scrape failed, coarse enough for retry logic and exit codes to
+/// branch on.
+#[derive(Debug, thiserror::Error)]
+pub enum ScrapeError {
+    /// The WebDriver could not be reached or the session died.
+    #[error("WebDriver connection failed: {0}")]
+    Connection(String),
+    #[error("timed out: {0}")]
+    NavigationTimeout(String),
+    #[error("element not found: {0}")]
+    SelectorMissing(String),
+    #[error("script error: {0}")]
+    Script(String),
+    /// The page loaded but is not the one the step expected, usually because
+    /// the site layout changed.
+    #[error("unexpected page: {0}")]
+    UnexpectedPage(String),
+    #[error("authentication required: {0}")]
+    AuthRequired(String),
+    /// The site wants something only a person can do, such as a two-step
+    /// verification code or a captcha.
+    #[error("manual action required: {0}")]
+    ManualActionRequired(String),
+    #[error("rate limited: {0}")]
+    RateLimited(String),
+    #[error("invalid configuration: {0}")]
+    Config(String),
+    #[error("WebDriver command failed: {0}")]
+    WebDriver(#[source] CmdError),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    #[error(transparent)]
+    Other(#[from] anyhow::Error),
+}
+
+/// The variant of a [`ScrapeError`] without its payload, for config such as
+/// which failures a retry policy retries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorKind {
+    Connection,
+    NavigationTimeout,
+    SelectorMissing,
+    Script,
+    UnexpectedPage,
+    AuthRequired,
+    ManualActionRequired,
+    RateLimited,
+    Config,
+    Other,
+}
+
+impl ScrapeError {
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            ScrapeError::Connection(_) => ErrorKind::Connection,
+            ScrapeError::NavigationTimeout(_) => ErrorKind::NavigationTimeout,
+            ScrapeError::SelectorMissing(_) => ErrorKind::SelectorMissing,
+            ScrapeError::Script(_) => ErrorKind::Script,
+            ScrapeError::UnexpectedPage(_) => ErrorKind::UnexpectedPage,
+            ScrapeError::AuthRequired(_) => ErrorKind::AuthRequired,
+            ScrapeError::ManualActionRequired(_) => ErrorKind::ManualActionRequired,
+            ScrapeError::RateLimited(_) => ErrorKind::RateLimited,
+            ScrapeError::Config(_) => ErrorKind::Config,
+            ScrapeError::WebDriver(_)
+            | ScrapeError::Io(_)
+            | ScrapeError::Json(_)
+            | ScrapeError::Other(_) => ErrorKind::Other,
+        }
+    }
+
+    /// Process exit code for this kind of failure.
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            ScrapeError::Connection(_) => 10,
+            ScrapeError::NavigationTimeout(_) => 11,
+            ScrapeError::SelectorMissing(_) => 12,
+            ScrapeError::Script(_) => 13,
+            ScrapeError::UnexpectedPage(_) => 14,
+            ScrapeError::AuthRequired(_) => 15,
+            ScrapeError::RateLimited(_) => 16,
+            ScrapeError::ManualActionRequired(_) => 17,
+            ScrapeError::Config(_) => 2,
+            ScrapeError::WebDriver(_)
+            | ScrapeError::Io(_)
+            | ScrapeError::Json(_)
+            | ScrapeError::Other(_) => 1,
+        }
+    }
+}
+
+impl From<NewSessionError> for ScrapeError {
+    fn from(e: NewSessionError) -> Self {
+        ScrapeError::Connection(e.to_string())
+    }
+}
+
+impl From<CmdError> for ScrapeError {
+    fn from(e: CmdError) -> Self {
+        match &e {
+            CmdError::NoSuchElement(wd) => ScrapeError::SelectorMissing(wd.message.to_string()),
+            CmdError::Lost(_) | CmdError::Failed(_) => ScrapeError::Connection(e.to_string()),
+            CmdError::WaitTimeout => ScrapeError::NavigationTimeout(e.to_string()),
+            CmdError::Standard(wd) => match wd.error() {
+                "timeout" => ScrapeError::NavigationTimeout(wd.message.to_string()),
+                "javascript error" | "script timeout" => {
+                    ScrapeError::Script(wd.message.to_string())
+                }
+                "invalid session id" | "session not created" => {
+                    ScrapeError::Connection(wd.message.to_string())
+                }
+                _ => ScrapeError::WebDriver(e),
+            },
+            _ => ScrapeError::WebDriver(e),
+        }
+    }
+}
@@ -0,0 +1,249 @@
+use crate::job_application::FormField;
+use anyhow::{bail, Context, Result};
+use regex::{Regex, RegexBuilder};
+use serde::Deserialize;
+use std::path::Path;
+
+/// The kinds of question the form filler can answer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QuestionKind {
+    Radio,
+    Select,
+    /// Any typed input: text, number, email, phone or a textarea.
+    Text,
+    Checkbox,
+}
+
+impl QuestionKind {
+    /// The kind of a captured field; `None` for inputs the filler leaves
+    /// alone, such as file uploads.
+    pub fn of(field: &FormField) -> Option<Self> {
+        match field.kind.as_str() {
+            "radio" => Some(QuestionKind::Radio),
+            "select" => Some(QuestionKind::Select),
+            "checkbox" => Some(QuestionKind::Checkbox),
+            "file" | "submit" | "button" | "reset" | "image" => None,
+            _ => Some(QuestionKind::Text),
+        }
+    }
+}
+
+/// One `[[answer]]` entry of the answers file, as written.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawRule {
+    /// Matches questions containing this text, ignoring case.
+    text: Option<String>,
+    /// Matches questions this regex finds a match in, ignoring case.
+    regex: Option<String>,
+    /// Only questions of this kind; any kind if unset.
+    kind: Option<QuestionKind>,
+    /// The option to pick for radios and selects, the text to type, or
+    /// `yes`/`no` for checkboxes.
+    answer: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct RawProfile {
+    #[serde(default)]
+    answer: Vec<RawRule>,
+}
+
+#[derive(Debug)]
+enum Matcher {
+    Text(String),
+    Regex(Regex),
+}
+
+#[derive(Debug)]
+struct Rule {
+    matcher: Matcher,
+    kind: Option<QuestionKind>,
+    answer: String,
+}
+
+impl Rule {
+    fn compile(raw: RawRule) -> Result<Self> {
+        let matcher = match (raw.text, raw.regex) {
+            (Some(text), None) => Matcher::Text(text.to_lowercase()),
+            (None, Some(pattern)) => Matcher::Regex(
+                RegexBuilder::new(&pattern)
+                    .case_insensitive(true)
+                    .build()
+                    .with_context(|| format!("invalid regex '{}'", pattern))?,
+            ),
+            _ => bail!("exactly one of 'text' and 'regex' must be set"),
+        };
+        Ok(Self {
+            matcher,
+            kind: raw.kind,
+            answer: raw.answer,
+        })
+    }
+
+    fn matches(&self, question: &str, kind: QuestionKind) -> bool {
+        if self.kind.is_some_and(|k| k != kind) {
+            return false;
+        }
+        match &self.matcher {
+            Matcher::Text(text) => question.to_lowercase().contains(text),
+            Matcher::Regex(regex) => regex.is_match(question),
+        }
+    }
+}
+
+/// Answers to Easy Apply questions, matched against each question's label in
+/// the order they are listed; the first match wins.
+#[derive(Debug, Default)]
+pub struct AnswerProfile {
+    rules: Vec<Rule>,
+}
+
+impl AnswerProfile {
+    /// Loads the answers file at `path`, or an empty profile if there is
+    /// none, in which case every question is reported as unanswered.
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            println!(
+                "No answers file at {}; form questions will not be filled",
+                path.display()
+            );
+            return Ok(Self::default());
+        }
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read answers file {}", path.display()))?;
+        let raw: RawProfile = toml::from_str(&text)
+            .with_context(|| format!("Failed to parse answers file {}", path.display()))?;
+
+        let rules = raw
+            .answer
+            .into_iter()
+            .enumerate()
+            .map(|(index, rule)| {
+                Rule::compile(rule)
+                    .with_context(|| format!("Invalid answer {} in {}", index + 1, path.display()))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        println!("Loaded {} answers from {}", rules.len(), path.display());
+        Ok(Self { rules })
+    }
+
+    /// The answer for `field`, if a rule covers it.
+    pub fn answer(&self, field: &FormField) -> Option<&str> {
+        let question = field.label.as_deref()?;
+        let kind = QuestionKind::of(field)?;
+        self.rules
+            .iter()
+            .find(|rule| rule.matches(question, kind))
+            .map(|rule| rule.answer.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(
+        text: Option<&str>,
+        regex: Option<&str>,
+        kind: Option<QuestionKind>,
+        answer: &str,
+    ) -> RawRule {
+        RawRule {
+            text: text.map(str::to_string),
+            regex: regex.map(str::to_string),
+            kind,
+            answer: answer.to_string(),
+        }
+    }
+
+    fn profile(rules: Vec<RawRule>) -> AnswerProfile {
+        AnswerProfile {
+            rules: rules
+                .into_iter()
+                .map(|r| Rule::compile(r).unwrap())
+                .collect(),
+        }
+    }
+
+    fn field(label: &str, kind: &str) -> FormField {
+        FormField {
+            label: Some(label.to_string()),
+            kind: kind.to_string(),
+            value: None,
+            required: true,
+            options: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn first_matching_rule_wins_ignoring_case() {
+        let answers = profile(vec![
+            rule(Some("Years of EXPERIENCE with Rust"), None, None, "5"),
+            rule(None, Some(r"years of \w+ experience"), None, "3"),
+            rule(Some("experience"), None, Some(QuestionKind::Radio), "Yes"),
+            rule(Some("experience"), None, None, "Plenty"),
+        ]);
+        assert_eq!(
+            answers.answer(&field(
+                "How many years of experience with rust do you have?",
+                "number"
+            )),
+            Some("5")
+        );
+        assert_eq!(
+            answers.answer(&field("Years of Python Experience", "text")),
+            Some("3")
+        );
+        assert_eq!(
+            answers.answer(&field("Do you have management experience?", "radio")),
+            Some("Yes")
+        );
+        assert_eq!(
+            answers.answer(&field("Do you have management experience?", "textarea")),
+            Some("Plenty")
+        );
+        assert_eq!(
+            answers.answer(&field("Are you authorized to work?", "radio")),
+            None
+        );
+        assert_eq!(answers.answer(&field("Experience", "file")), None);
+        assert_eq!(
+            answers.answer(&FormField {
+                label: None,
+                ..field("", "text")
+            }),
+            None
+        );
+    }
+
+    #[test]
+    fn rules_need_exactly_one_of_text_and_regex() {
+        assert!(Rule::compile(rule(Some("a"), Some("b"), None, "x")).is_err());
+        assert!(Rule::compile(rule(None, None, None, "x")).is_err());
+    }
+
+    #[test]
+    fn load_reports_which_rule_has_a_bad_regex() {
+        let dir = std::env::temp_dir().join(format!("rwa-answers-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("answers.toml");
+        std::fs::write(
+            &path,
+            "[[answer]]\ntext = \"sponsorship\"\nanswer = \"No\"\n\n\
+             [[answer]]\nregex = \"years (of\"\nanswer = \"5\"\n",
+        )
+        .unwrap();
+
+        let error = format!("{:#}", AnswerProfile::load(&path).unwrap_err());
+        assert!(error.starts_with("Invalid answer 2 in "), "{}", error);
+        assert!(error.contains("invalid regex 'years (of'"), "{}", error);
+
+        assert!(AnswerProfile::load(&dir.join("missing.toml"))
+            .unwrap()
+            .rules
+            .is_empty());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,256 @@
+use crate::job_listing::JobListing;
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+
+/// What to do once an application reaches its final submit button.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SubmitMode {
+    #[default]
+    Submit,
+    /// Walk the whole flow but stop before submitting, leaving the ledger
+    /// untouched.
+    DryRun,
+    /// Show the job and the filled form, and ask before each submit.
+    Confirm,
+}
+
+/// The terminal state of an application walk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ApplyResult {
+    Submitted,
+    /// Stopped at the submit button because of [`SubmitMode::DryRun`].
+    DryRun,
+    /// Declined at the [`SubmitMode::Confirm`] prompt.
+    Declined,
+    /// A required question had no answer in the answer profile.
+    Unanswered,
+    /// Advancing left the modal on the same page, usually because of a
+    /// validation error.
+    Stuck,
+    /// The modal had more pages than the configured cap.
+    PageLimit,
+    /// The modal showed an error instead of a form.
+    Error,
+    /// The modal closed before the application was submitted.
+    Closed,
+}
+
+impl ApplyResult {
+    pub fn description(self) -> &'static str {
+        match self {
+            ApplyResult::Submitted => "submitted",
+            ApplyResult::DryRun => "stopped before submitting (dry run)",
+            ApplyResult::Declined => "submission declined at confirmation",
+            ApplyResult::Unanswered => "required questions have no answer in the answer profile",
+            ApplyResult::Stuck => "the form did not advance past a page",
+            ApplyResult::PageLimit => "the form has more pages than max_form_pages",
+            ApplyResult::Error => "the modal showed an error",
+            ApplyResult::Closed => "the modal closed before the application was submitted",
+        }
+    }
+}
+
+/// Which page of the Easy Apply modal is showing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ModalPage {
+    ContactInfo,
+    Resume,
+    /// Screening questions, or any other page of inputs.
+    Questions,
+    /// The summary with the "Submit application" button.
+    Review,
+    /// The confirmation shown once the application was sent.
+    Submitted,
+    Error,
+    /// No modal is open.
+    Closed,
+    /// A modal without inputs the filler recognizes.
+    Unknown,
+}
+
+/// What the page detector saw on one visit to the modal.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModalState {
+    pub page: ModalPage,
+    pub heading: Option<String>,
+    /// The progress bar's value in percent, if shown.
+    pub progress: Option<u32>,
+    /// The label of the footer's primary button.
+    pub primary_button: Option<String>,
+    #[serde(default)]
+    pub errors: Vec<String>,
+}
+
+impl ModalState {
+    /// Whether `other` looks like the same page, i.e. advancing did nothing.
+    pub fn same_page(&self, other: &ModalState) -> bool {
+        self.page == other.page && self.heading == other.heading && self.progress == other.progress
+    }
+}
+
+/// One input of an Easy Apply page as the page script reports it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FormField {
+    pub label: Option<String>,
+    /// The input type, or `select`/`textarea`.
+    pub kind: String,
+    /// The selected option's text for selects and radio groups.
+    pub value: Option<String>,
+    #[serde(default)]
+    pub required: bool,
+    /// The choices of radio groups and selects.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub options: Vec<String>,
+}
+
+impl FormField {
+    /// Whether nothing has been entered or chosen yet.
+    pub fn is_empty(&self) -> bool {
+        match self.value.as_deref() {
+            None | Some("") => true,
+            Some("false") => self.kind == "checkbox",
+            Some(_) => false,
+        }
+    }
+}
+
+/// A question the form filler left alone, reported instead of guessed.
+#[derive(Debug, Clone, Serialize)]
+pub struct UnansweredQuestion {
+    pub page: u32,
+    pub label: Option<String>,
+    pub kind: String,
+    pub required: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub options: Vec<String>,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FormPage {
+    #[serde(default)]
+    pub index: u32,
+    pub heading: Option<String>,
+    pub fields: Vec<FormField>,
+}
+
+/// Every page of one application walk with the values it was left with,
+/// written to the run's data directory whether or not it was submitted.
+#[derive(Debug, Serialize)]
+pub struct ApplicationCapture {
+    pub job: JobListing,
+    pub mode: SubmitMode,
+    pub pages: Vec<FormPage>,
+    /// Every modal state visited, in order; the last is where the walk ended.
+    pub states: Vec<ModalState>,
+    pub unanswered: Vec<UnansweredQuestion>,
+    pub result: Option<ApplyResult>,
+    pub started_at: DateTime<Local>,
+}
+
+impl ApplicationCapture {
+    pub fn new(job: JobListing, mode: SubmitMode) -> Self {
+        Self {
+            job,
+            mode,
+            pages: Vec::new(),
+            states: Vec::new(),
+            unanswered: Vec::new(),
+            result: None,
+            started_at: Local::now(),
+        }
+    }
+
+    pub fn push_page(&mut self, mut page: FormPage) {
+        page.index = self.pages.len() as u32 + 1;
+        self.pages.push(page);
+    }
+
+    /// The job and every filled value, for the confirmation prompt.
+    pub fn summary(&self) -> String {
+        let job = &self.job;
+        let mut lines = vec![format!(
+            "{} at {}",
+            job.title.as_deref().unwrap_or("Untitled job"),
+            job.company.as_deref().unwrap_or("unknown company")
+        )];
+        if let Some(location) = &job.location {
+            lines.push(format!("  Location: {}", location));
+        }
+        if let Some(workplace) = job.workplace_type {
+            lines.push(format!("  Workplace: {:?}", workplace));
+        }
+        lines.push(format!("  {}", job.url));
+        for page in &self.pages {
+            lines.push(format!(
+                "Page {}: {}",
+                page.index,
+                page.heading.as_deref().unwrap_or("")
+            ));
+            for field in &page.fields {
+                lines.push(format!(
+                    "  {}{}: {}",
+                    field.label.as_deref().unwrap_or("(unlabelled)"),
+                    if field.required { " *" } else { "" },
+                    field.value.as_deref().unwrap_or("(empty)")
+                ));
+            }
+        }
+        lines.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn field(kind: &str, value: Option<&str>) -> FormField {
+        FormField {
+            label: Some("Question".to_string()),
+            kind: kind.to_string(),
+            value: value.map(str::to_string),
+            required: true,
+            options: Vec::new(),
+        }
+    }
+
+    fn state(page: ModalPage, heading: &str, progress: Option<u32>) -> ModalState {
+        ModalState {
+            page,
+            heading: Some(heading.to_string()),
+            progress,
+            primary_button: Some("Next".to_string()),
+            errors: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn fields_without_a_value_or_unchecked_checkboxes_are_empty() {
+        assert!(field("text", None).is_empty());
+        assert!(field("text", Some("")).is_empty());
+        assert!(field("checkbox", Some("false")).is_empty());
+        assert!(!field("checkbox", Some("true")).is_empty());
+        assert!(!field("text", Some("false")).is_empty());
+        assert!(!field("select", Some("Yes")).is_empty());
+    }
+
+    #[test]
+    fn same_page_compares_page_heading_and_progress() {
+        let questions = state(ModalPage::Questions, "Additional questions", Some(50));
+        let mut retried = questions.clone();
+        retried.primary_button = Some("Review".to_string());
+        retried.errors = vec!["Please enter a valid answer".to_string()];
+        assert!(questions.same_page(&retried));
+
+        assert!(!questions.same_page(&state(
+            ModalPage::Questions,
+            "Additional questions",
+            Some(75)
+        )));
+        assert!(!questions.same_page(&state(ModalPage::Questions, "Work authorization", Some(50))));
+        assert!(!questions.same_page(&state(ModalPage::Review, "Additional questions", Some(50))));
+    }
+}
@@ -0,0 +1,229 @@
+use crate::common::write_atomic;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// How far an application got.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum JobOutcome {
+    Submitted,
+    /// The flow ended without a submit button, or the job was deliberately
+    /// left alone.
+    Skipped {
+        reason: String,
+    },
+    /// The submission was declined at the confirmation prompt, so it may be
+    /// offered again with `retry_failed`.
+    Declined,
+    Failed {
+        step: String,
+        error: String,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LedgerEntry {
+    pub job_id: String,
+    pub title: Option<String>,
+    pub company: Option<String>,
+    pub url: String,
+    pub outcome: JobOutcome,
+    pub first_attempted: DateTime<Local>,
+    pub last_attempted: DateTime<Local>,
+    pub attempts: u32,
+}
+
+/// Every job the scraper has acted on, across iterations and runs, saved
+/// after each change so an interrupted run loses nothing.
+pub struct JobLedger {
+    path: PathBuf,
+    jobs: BTreeMap<String, LedgerEntry>,
+    /// Whether jobs that failed may be picked again.
+    retry_failed: bool,
+}
+
+impl JobLedger {
+    /// Loads the ledger at `path`, or starts an empty one.
+    pub fn load(path: &Path, retry_failed: bool) -> Result<Self> {
+        let jobs = if path.exists() {
+            let json = std::fs::read_to_string(path)
+                .with_context(|| format!("Failed to read job ledger {}", path.display()))?;
+            serde_json::from_str(&json)
+                .with_context(|| format!("Failed to parse job ledger {}", path.display()))?
+        } else {
+            BTreeMap::new()
+        };
+        Ok(Self {
+            path: path.to_path_buf(),
+            jobs,
+            retry_failed,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// IDs of jobs not to act on again.
+    pub fn handled_ids(&self) -> Vec<String> {
+        self.jobs
+            .values()
+            .filter(|entry| {
+                !(self.retry_failed
+                    && matches!(
+                        entry.outcome,
+                        JobOutcome::Failed { .. } | JobOutcome::Declined
+                    ))
+            })
+            .map(|entry| entry.job_id.clone())
+            .collect()
+    }
+
+    pub fn record(
+        &mut self,
+        job: &JobRef,
+        outcome: JobOutcome,
+        now: DateTime<Local>,
+    ) -> Result<()> {
+        let entry = self
+            .jobs
+            .entry(job.id.clone())
+            .and_modify(|entry| {
+                entry.attempts += 1;
+                entry.last_attempted = now;
+            })
+            .or_insert_with(|| LedgerEntry {
+                job_id: job.id.clone(),
+                title: None,
+                company: None,
+                url: job.url.clone(),
+                outcome: outcome.clone(),
+                first_attempted: now,
+                last_attempted: now,
+                attempts: 1,
+            });
+        entry.title = job.title.clone().or(entry.title.take());
+        entry.company = job.company.clone().or(entry.company.take());
+        entry.outcome = outcome;
+        self.save()
+    }
+
+    fn save(&self) -> Result<()> {
+        write_atomic(
+            &self.path,
+            serde_json::to_string_pretty(&self.jobs)?.as_bytes(),
+        )
+        .with_context(|| format!("Failed to write job ledger {}", self.path.display()))?;
+        Ok(())
+    }
+}
+
+/// The job currently selected in the search results.
+#[derive(Debug, Clone)]
+pub struct JobRef {
+    pub id: String,
+    pub title: Option<String>,
+    pub company: Option<String>,
+    pub url: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn ledger(name: &str, retry_failed: bool) -> JobLedger {
+        let path = std::env::temp_dir().join(format!(
+            "rwa-ledger-test-{}-{}/ledger.json",
+            std::process::id(),
+            name
+        ));
+        let _ = std::fs::remove_file(&path);
+        JobLedger::load(&path, retry_failed).unwrap()
+    }
+
+    fn cleanup(ledger: &JobLedger) {
+        std::fs::remove_dir_all(ledger.path().parent().unwrap()).unwrap();
+    }
+
+    fn job(id: &str, title: Option<&str>) -> JobRef {
+        JobRef {
+            id: id.to_string(),
+            title: title.map(str::to_string),
+            company: title.map(|_| "Acme".to_string()),
+            url: format!("https://www.linkedin.com/jobs/view/{}/", id),
+        }
+    }
+
+    fn at(hour: u32) -> DateTime<Local> {
+        Local.with_ymd_and_hms(2024, 3, 15, hour, 0, 0).unwrap()
+    }
+
+    fn failed() -> JobOutcome {
+        JobOutcome::Failed {
+            step: "fill_form_page".to_string(),
+            error: "unanswered question".to_string(),
+        }
+    }
+
+    /// Records one job per outcome and returns the handled IDs, sorted.
+    fn handled(name: &str, retry_failed: bool) -> Vec<String> {
+        let mut ledger = ledger(name, retry_failed);
+        let outcomes = [
+            ("1", JobOutcome::Submitted),
+            (
+                "2",
+                JobOutcome::Skipped {
+                    reason: "not Easy Apply".to_string(),
+                },
+            ),
+            ("3", JobOutcome::Declined),
+            ("4", failed()),
+        ];
+        for (id, outcome) in outcomes {
+            ledger.record(&job(id, None), outcome, at(9)).unwrap();
+        }
+        let mut ids = ledger.handled_ids();
+        ids.sort();
+        cleanup(&ledger);
+        ids
+    }
+
+    #[test]
+    fn handled_ids_include_everything_without_retry_failed() {
+        assert_eq!(handled("no-retry", false), ["1", "2", "3", "4"]);
+    }
+
+    #[test]
+    fn handled_ids_leave_failed_and_declined_jobs_to_retry() {
+        assert_eq!(handled("retry", true), ["1", "2"]);
+    }
+
+    #[test]
+    fn record_counts_attempts_and_keeps_known_details() {
+        let mut ledger = ledger("record", true);
+        ledger
+            .record(&job("1", Some("Rust Engineer")), failed(), at(9))
+            .unwrap();
+        ledger
+            .record(&job("1", None), JobOutcome::Submitted, at(10))
+            .unwrap();
+
+        // Reloaded from disk, so the save is covered as well.
+        let ledger = JobLedger::load(ledger.path(), true).unwrap();
+        let entry = &ledger.jobs["1"];
+        assert_eq!(entry.attempts, 2);
+        assert_eq!(
+            (entry.first_attempted, entry.last_attempted),
+            (at(9), at(10))
+        );
+        assert_eq!(entry.outcome, JobOutcome::Submitted);
+        assert_eq!(entry.title.as_deref(), Some("Rust Engineer"));
+        assert_eq!(entry.company.as_deref(), Some("Acme"));
+        assert_eq!(ledger.handled_ids(), ["1"]);
+        cleanup(&ledger);
+    }
+}
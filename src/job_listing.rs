@@ -0,0 +1,338 @@
+use chrono::{Local, NaiveDate, TimeDelta};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WorkplaceType {
+    OnSite,
+    Remote,
+    Hybrid,
+}
+
+/// A result card as the page script reports it.
+#[derive(Debug, Deserialize)]
+pub struct RawJobCard {
+    pub id: Option<String>,
+    pub title: Option<String>,
+    pub company: Option<String>,
+    /// The caption under the company, e.g. `Berlin, Germany (Hybrid)`.
+    pub location: Option<String>,
+    /// The `datetime` attribute of the card's `<time>`, if any.
+    pub posted_datetime: Option<String>,
+    /// The card's footer text, e.g. `2 weeks ago`.
+    pub posted: Option<String>,
+    #[serde(default)]
+    pub easy_apply: bool,
+}
+
+/// The details pane of the selected job.
+#[derive(Debug, Deserialize)]
+pub struct RawJobDetails {
+    pub title: Option<String>,
+    pub company: Option<String>,
+    /// The pane's insight lines and pills, e.g. `Remote`, `Full-time`.
+    #[serde(default)]
+    pub insights: Vec<String>,
+    /// The pane's description line, e.g. `Berlin · 2 weeks ago · Over 100 applicants`.
+    pub summary: Option<String>,
+    pub apply_label: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobListing {
+    pub id: String,
+    pub title: Option<String>,
+    pub company: Option<String>,
+    pub location: Option<String>,
+    pub workplace_type: Option<WorkplaceType>,
+    pub posted_date: Option<NaiveDate>,
+    pub applicant_count: Option<u32>,
+    pub easy_apply: bool,
+    pub url: String,
+    /// Whether the details pane was read; otherwise only the card was.
+    pub inspected: bool,
+    #[serde(default)]
+    pub warnings: Vec<String>,
+}
+
+impl JobListing {
+    /// A listing known only by its ID, e.g. one selected outside the
+    /// extracted cards.
+    pub fn new(id: String) -> Self {
+        JobListing {
+            url: job_url(&id),
+            id,
+            title: None,
+            company: None,
+            location: None,
+            workplace_type: None,
+            posted_date: None,
+            applicant_count: None,
+            easy_apply: false,
+            inspected: false,
+            warnings: Vec::new(),
+        }
+    }
+
+    /// Cards without a job ID cannot be linked to or deduplicated, so they
+    /// are dropped.
+    pub fn from_card(raw: RawJobCard) -> Option<Self> {
+        let id = non_empty(raw.id)?;
+        let mut warnings = Vec::new();
+
+        let location = non_empty(raw.location);
+        let workplace_type = location.as_deref().and_then(parse_workplace);
+        let posted_date = match non_empty(raw.posted_datetime) {
+            Some(text) => NaiveDate::parse_from_str(&text, "%Y-%m-%d")
+                .map_err(|_| warnings.push(format!("unrecognized posted date '{}'", text)))
+                .ok(),
+            None => non_empty(raw.posted)
+                .and_then(|text| parse_relative_date(&text, Local::now().date_naive())),
+        };
+
+        Some(JobListing {
+            title: non_empty(raw.title),
+            company: non_empty(raw.company),
+            location,
+            workplace_type,
+            posted_date,
+            easy_apply: raw.easy_apply,
+            warnings,
+            ..JobListing::new(id)
+        })
+    }
+
+    /// Fills in what only the details pane shows, preferring it where the
+    /// card and pane disagree.
+    pub fn merge_details(&mut self, raw: RawJobDetails) {
+        self.title = non_empty(raw.title).or(self.title.take());
+        self.company = non_empty(raw.company).or(self.company.take());
+
+        if let Some(workplace) = raw.insights.iter().find_map(|text| parse_workplace(text)) {
+            self.workplace_type = Some(workplace);
+        }
+
+        let summary = non_empty(raw.summary).unwrap_or_default();
+        for part in summary.split('·').map(str::trim) {
+            let lower = part.to_ascii_lowercase();
+            if lower.contains("applicant") {
+                match parse_first_number(part) {
+                    Some(count) => self.applicant_count = Some(count),
+                    None => self
+                        .warnings
+                        .push(format!("unrecognized applicant count '{}'", part)),
+                }
+            } else if lower.ends_with("ago") {
+                if let Some(date) = parse_relative_date(part, Local::now().date_naive()) {
+                    self.posted_date = Some(date);
+                }
+            }
+        }
+
+        if let Some(label) = non_empty(raw.apply_label) {
+            self.easy_apply = label.to_ascii_lowercase().contains("easy apply");
+        }
+        self.inspected = true;
+    }
+}
+
+pub fn job_url(id: &str) -> String {
+    format!("https://www.linkedin.com/jobs/view/{}/", id)
+}
+
+fn non_empty(value: Option<String>) -> Option<String> {
+    value
+        .map(|v| v.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|v| !v.is_empty())
+}
+
+fn parse_workplace(text: &str) -> Option<WorkplaceType> {
+    let lower = text.to_ascii_lowercase();
+    if lower.contains("remote") {
+        Some(WorkplaceType::Remote)
+    } else if lower.contains("hybrid") {
+        Some(WorkplaceType::Hybrid)
+    } else if lower.contains("on-site") || lower.contains("onsite") {
+        Some(WorkplaceType::OnSite)
+    } else {
+        None
+    }
+}
+
+/// `Over 100 applicants` and `1,204 applicants` both yield their number.
+fn parse_first_number(text: &str) -> Option<u32> {
+    let start = text.find(|c: char| c.is_ascii_digit())?;
+    let digits: String = text[start..]
+        .chars()
+        .take_while(|c| c.is_ascii_digit() || *c == ',')
+        .filter(char::is_ascii_digit)
+        .collect();
+    digits.parse().ok()
+}
+
+/// Turns `3 days ago` or `Reposted 2 weeks ago` into a date relative to
+/// `today`. Counts too large for a date are not dates.
+fn parse_relative_date(text: &str, today: NaiveDate) -> Option<NaiveDate> {
+    let lower = text.to_ascii_lowercase();
+    let words: Vec<&str> = lower.split_whitespace().collect();
+    let ago = words.iter().position(|w| *w == "ago")?;
+    let unit = words.get(ago.checked_sub(1)?)?;
+    let count: i64 = words.get(ago.checked_sub(2)?)?.parse().ok()?;
+    let days = match unit.trim_end_matches('s') {
+        "minute" | "hour" => 0,
+        "day" => count,
+        "week" => count.checked_mul(7)?,
+        "month" => count.checked_mul(30)?,
+        "year" => count.checked_mul(365)?,
+        _ => return None,
+    };
+    today.checked_sub_signed(TimeDelta::try_days(days)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(text: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(text, "%Y-%m-%d").unwrap()
+    }
+
+    fn card(id: Option<&str>) -> RawJobCard {
+        RawJobCard {
+            id: id.map(str::to_string),
+            title: Some("  Rust   Engineer \n".to_string()),
+            company: Some("Acme".to_string()),
+            location: Some("Berlin, Germany (Hybrid)".to_string()),
+            posted_datetime: None,
+            posted: None,
+            easy_apply: true,
+        }
+    }
+
+    fn details() -> RawJobDetails {
+        RawJobDetails {
+            title: None,
+            company: None,
+            insights: Vec::new(),
+            summary: None,
+            apply_label: None,
+        }
+    }
+
+    #[test]
+    fn parse_relative_date_counts_back_from_today() {
+        let today = date("2024-03-15");
+        let cases = [
+            ("5 minutes ago", "2024-03-15"),
+            ("1 hour ago", "2024-03-15"),
+            ("1 day ago", "2024-03-14"),
+            ("3 days ago", "2024-03-12"),
+            ("1 week ago", "2024-03-08"),
+            ("Reposted 2 weeks ago", "2024-03-01"),
+            ("1 month ago", "2024-02-14"),
+            ("2 months ago", "2024-01-15"),
+            ("1 year ago", "2023-03-16"),
+            ("2 Years Ago", "2022-03-16"),
+        ];
+        for (text, expected) in cases {
+            assert_eq!(
+                parse_relative_date(text, today),
+                Some(date(expected)),
+                "{}",
+                text
+            );
+        }
+    }
+
+    #[test]
+    fn parse_relative_date_rejects_garbage_and_huge_counts() {
+        let today = date("2024-03-15");
+        for text in [
+            "",
+            "ago",
+            "days ago",
+            "a few days ago",
+            "3 fortnights ago",
+            "3 days",
+            "300000 years ago",
+            "99999999999 days ago",
+            "9223372036854775807 years ago",
+        ] {
+            assert_eq!(parse_relative_date(text, today), None, "{}", text);
+        }
+    }
+
+    #[test]
+    fn parse_first_number_skips_text_and_thousands_separators() {
+        assert_eq!(parse_first_number("Over 100 applicants"), Some(100));
+        assert_eq!(parse_first_number("1,204 applicants"), Some(1204));
+        assert_eq!(parse_first_number("applicants"), None);
+        assert_eq!(parse_first_number("99999999999 applicants"), None);
+    }
+
+    #[test]
+    fn from_card_cleans_text_and_drops_cards_without_id() {
+        assert!(JobListing::from_card(card(None)).is_none());
+        assert!(JobListing::from_card(card(Some("  "))).is_none());
+
+        let listing = JobListing::from_card(RawJobCard {
+            posted_datetime: Some("2024-03-01".to_string()),
+            ..card(Some("3812345678"))
+        })
+        .unwrap();
+        assert_eq!(listing.id, "3812345678");
+        assert_eq!(
+            listing.url,
+            "https://www.linkedin.com/jobs/view/3812345678/"
+        );
+        assert_eq!(listing.title.as_deref(), Some("Rust Engineer"));
+        assert_eq!(listing.workplace_type, Some(WorkplaceType::Hybrid));
+        assert_eq!(listing.posted_date, Some(date("2024-03-01")));
+        assert!(listing.easy_apply);
+        assert!(!listing.inspected);
+        assert!(listing.warnings.is_empty());
+
+        let listing = JobListing::from_card(RawJobCard {
+            posted_datetime: Some("last Tuesday".to_string()),
+            ..card(Some("1"))
+        })
+        .unwrap();
+        assert_eq!(listing.posted_date, None);
+        assert_eq!(
+            listing.warnings,
+            ["unrecognized posted date 'last Tuesday'"]
+        );
+    }
+
+    #[test]
+    fn merge_details_prefers_the_pane_and_keeps_card_values() {
+        let mut listing = JobListing::from_card(card(Some("1"))).unwrap();
+        listing.merge_details(RawJobDetails {
+            title: Some("Senior Rust Engineer".to_string()),
+            insights: vec!["Remote".to_string(), "Full-time".to_string()],
+            summary: Some("Berlin · 0 minutes ago · Over 100 applicants".to_string()),
+            apply_label: Some("Apply".to_string()),
+            ..details()
+        });
+        assert_eq!(listing.title.as_deref(), Some("Senior Rust Engineer"));
+        assert_eq!(listing.company.as_deref(), Some("Acme"));
+        assert_eq!(listing.workplace_type, Some(WorkplaceType::Remote));
+        assert_eq!(listing.applicant_count, Some(100));
+        assert_eq!(listing.posted_date, Some(Local::now().date_naive()));
+        assert!(!listing.easy_apply);
+        assert!(listing.inspected);
+
+        let mut listing = JobListing::from_card(card(Some("2"))).unwrap();
+        listing.merge_details(RawJobDetails {
+            summary: Some("Berlin · Be an early applicant".to_string()),
+            ..details()
+        });
+        assert_eq!(listing.workplace_type, Some(WorkplaceType::Hybrid));
+        assert_eq!(listing.applicant_count, None);
+        assert!(listing.easy_apply);
+        assert_eq!(
+            listing.warnings,
+            ["unrecognized applicant count 'Be an early applicant'"]
+        );
+    }
+}
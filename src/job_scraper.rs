@@ -1,25 +1,395 @@
-use anyhow::{anyhow, Result};
-use fantoccini::{Client, Locator};
+use crate::artifacts::ArtifactKind;
+use crate::click::{ClickMethod, ClickTarget};
+use crate::common::BrowserSession;
+use crate::credentials::Credentials;
+use crate::debug_println;
+use crate::error::{Result, ScrapeError};
+use crate::job_answers::{AnswerProfile, QuestionKind};
+use crate::job_application::{
+    ApplicationCapture, ApplyResult, FormPage, ModalPage, ModalState, SubmitMode,
+    UnansweredQuestion,
+};
+use crate::job_ledger::{JobLedger, JobOutcome, JobRef};
+use crate::job_listing::{JobListing, RawJobCard, RawJobDetails};
+use crate::login_state::{Indicates, LoginDetector, LoginSignal, LoginState, SignalKind};
+use crate::output::Output;
+use crate::retry::RetryPolicy;
+use crate::scraper::{ConfigField, ScrapeContext, ScrapeReport, Scraper};
+use crate::selectors::SelectorCatalog;
+use crate::step::StepOutcome;
+use async_trait::async_trait;
+use chrono::Local;
 use image::{GenericImageView, Rgba};
-use serde_json::Value;
+use serde::Deserialize;
+use serde_json::json;
+use std::collections::HashSet;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Mutex;
 use std::time::Duration;
-use tokio::try_join;
 
-pub struct JobScraper {
-    client: Client,
+/// How long the network must be quiet before a page counts as settled.
+const NETWORK_QUIET: Duration = Duration::from_millis(500);
+const JOBS_HOME: &str = "https://www.linkedin.com/jobs/";
+const DEFAULT_LEDGER: &str = "job-ledger.json";
+const DEFAULT_ANSWERS: &str = "answers.toml";
+const DEFAULT_CREDENTIALS: &str = "linkedin-credentials.toml";
+/// The built-in selector catalog; the `selectors` setting overrides entries.
+const DEFAULT_SELECTORS: &str = include_str!("../selectors/jobs.toml");
+/// Credentials in the environment are read from `LINKEDIN_USERNAME` and
+/// `LINKEDIN_PASSWORD`.
+const CREDENTIALS_ENV_PREFIX: &str = "LINKEDIN";
+/// How far the logged-in and logged-out scores must be apart to call either.
+const DEFAULT_LOGIN_THRESHOLD: u32 = 3;
+
+/// Defines `modalForm(sel)`, which lists the questions on the open Easy
+/// Apply modal page along with the inputs that answer them. Radio buttons
+/// sharing a name are one question. `sel` holds the `modal` and
+/// `modal_heading` selector chains.
+const MODAL_FORM: &str = r#"
+const modalForm = sel => {
+    const modal = pick(sel.modal);
+    if (!modal) return null;
+    const clean = text => text ? text.replace(/\s+/g, ' ').trim() : null;
+    const labelFor = el => el.id ? modal.querySelector(`label[for="${CSS.escape(el.id)}"]`) : null;
+    const labelOf = el => {
+        const legend = el.closest('fieldset') && el.closest('fieldset').querySelector('legend');
+        const label = (el.type === 'radio' && legend) || labelFor(el) || el.closest('label') || legend;
+        return label ? clean(label.textContent) : el.getAttribute('aria-label') || el.name || null;
+    };
+    const isRequired = el => el.required || el.getAttribute('aria-required') === 'true';
+
+    const questions = [];
+    const radioGroups = new Map();
+    for (const el of modal.querySelectorAll('input, select, textarea')) {
+        if (el.type === 'hidden' || el.disabled) continue;
+        if (el.type === 'radio') {
+            const key = el.name || labelOf(el);
+            if (!radioGroups.has(key)) {
+                const group = { label: labelOf(el), kind: 'radio', value: null, required: isRequired(el), options: [], elements: [] };
+                radioGroups.set(key, group);
+                questions.push(group);
+            }
+            const group = radioGroups.get(key);
+            const option = labelFor(el);
+            const text = option ? clean(option.textContent) : el.value;
+            group.options.push(text);
+            group.elements.push(el);
+            if (el.checked) group.value = text;
+            continue;
+        }
+        let value;
+        let options = [];
+        if (el.tagName === 'SELECT') {
+            options = Array.from(el.options, option => clean(option.textContent));
+            value = el.selectedIndex > 0 ? options[el.selectedIndex] : null;
+        } else if (el.type === 'checkbox') {
+            value = String(el.checked);
+        } else if (el.type === 'file') {
+            value = el.files.length ? el.files[0].name : null;
+        } else {
+            value = el.value || null;
+        }
+        questions.push({
+            label: labelOf(el),
+            kind: el.tagName === 'INPUT' ? el.type : el.tagName.toLowerCase(),
+            value,
+            required: isRequired(el),
+            options,
+            elements: [el],
+        });
+    }
+
+    const heading = pick(sel.modal_heading, modal);
+    return { heading: heading ? clean(heading.textContent) : null, questions };
+};
+"#;
+
+/// Defines `jobIdOf(item, chain)`, the job ID of a results list item: the
+/// first `data-*job-id` attribute of the item, or of the element inside it,
+/// matching the first selector of `chain` that matches either.
+const JOB_ID_OF: &str = r#"
+const jobIdOf = (item, chain) => {
+    for (const selector of chain) {
+        const el = item.matches(selector) ? item : item.querySelector(selector);
+        if (!el) continue;
+        const attribute = Array.from(el.attributes)
+            .find(attr => attr.name.startsWith('data-') && attr.name.endsWith('job-id') && attr.value);
+        if (attribute) return attribute.value;
+    }
+    return null;
+};
+"#;
+
+/// How LinkedIn pages show whether the session is logged in, overridable with
+/// the `login_signals` setting. Job pages are also shown to guests, so the
+/// URL alone says little; the member navigation bar and the `li_at` session
+/// cookie say the most.
+fn default_login_signals() -> Vec<LoginSignal> {
+    use Indicates::*;
+    use SignalKind::*;
+    vec![
+        LoginSignal::new(
+            "member nav photo",
+            Selector,
+            ".global-nav__me, img.global-nav__me-photo",
+            LoggedIn,
+            3,
+        ),
+        LoginSignal::new("member nav bar", Selector, "#global-nav", LoggedIn, 1),
+        LoginSignal::new("session cookie", Cookie, "li_at", LoggedIn, 2),
+        LoginSignal::new(
+            "feed page",
+            Url,
+            r"^https://www\.linkedin\.com/feed/",
+            LoggedIn,
+            2,
+        ),
+        LoginSignal::new(
+            "login form",
+            Selector,
+            "input[name='session_key'], form.login__form",
+            LoggedOut,
+            3,
+        ),
+        LoginSignal::new(
+            "guest sign-in link",
+            Selector,
+            "a[data-tracking-control-name$='nav-header-signin']",
+            LoggedOut,
+            3,
+        ),
+        LoginSignal::new(
+            "login or authwall page",
+            Url,
+            r"^https://www\.linkedin\.com/(login|authwall|signup|uas/login)",
+            LoggedOut,
+            3,
+        ),
+        LoginSignal::new(
+            "checkpoint page",
+            Url,
+            r"/checkpoint/|/challenge/|/uas/consumer-captcha",
+            Challenge,
+            3,
+        ),
+        LoginSignal::new(
+            "verification code input",
+            Selector,
+            "input[name='pin'], input[id$='_verification_pin']",
+            Challenge,
+            3,
+        ),
+        LoginSignal::new(
+            "captcha",
+            Selector,
+            "#captcha-internal, iframe[src*='captcha']",
+            Challenge,
+            3,
+        ),
+        LoginSignal::new(
+            "verification text",
+            Text,
+            "two-step verification|verification code|security check|verify your identity",
+            Challenge,
+            2,
+        ),
+    ]
+}
+
+/// What the fill script reports for one answer.
+#[derive(Debug, Deserialize)]
+struct FillResult {
+    index: usize,
+    filled: bool,
+    detail: Option<String>,
 }
 
-impl JobScraper {
-    pub fn new(client: Client) -> Self {
-        Self { client }
+pub struct JobScraper;
+
+#[async_trait]
+impl Scraper for JobScraper {
+    fn name(&self) -> &'static str {
+        "jobs"
+    }
+
+    fn description(&self) -> &'static str {
+        "Run the LinkedIn Easy Apply job scraper"
+    }
+
+    fn config_schema(&self) -> Vec<ConfigField> {
+        vec![
+            ConfigField {
+                key: "iterations",
+                description: "Number of application attempts per run",
+                default: "10",
+            },
+            ConfigField {
+                key: "credentials_file",
+                description: "TOML file with username and password, readable only by you; LINKEDIN_USERNAME and LINKEDIN_PASSWORD take precedence",
+                default: DEFAULT_CREDENTIALS,
+            },
+            ConfigField {
+                key: "ledger",
+                description: "Jobs already acted on, skipped in later iterations and runs",
+                default: DEFAULT_LEDGER,
+            },
+            ConfigField {
+                key: "inspect_listings",
+                description:
+                    "Open every listed job to read its details pane, not just the one applied to",
+                default: "false",
+            },
+            ConfigField {
+                key: "retry_failed",
+                description: "Pick jobs whose application failed or was declined at confirmation last time again",
+                default: "false",
+            },
+            ConfigField {
+                key: "answers",
+                description: "Answers to Easy Apply form questions, matched by question text",
+                default: DEFAULT_ANSWERS,
+            },
+            ConfigField {
+                key: "max_form_pages",
+                description: "Easy Apply modal pages to walk before giving up on a job",
+                default: "10",
+            },
+            ConfigField {
+                key: "submit_mode",
+                description: "submit, dry_run (stop before the final submit) or confirm (ask before each submit)",
+                default: "submit",
+            },
+            ConfigField {
+                key: "selectors",
+                description: "TOML file of selectors replacing built-in ones by element name; see selectors/jobs.toml",
+                default: "built in",
+            },
+            ConfigField {
+                key: "discovery_card_point",
+                description: "[x, y] viewport point clicked when the discovery card cannot be found by selector; unset, that fails the iteration",
+                default: "unset",
+            },
+            ConfigField {
+                key: "login_signals",
+                description: "Weighted signals telling logged-in pages from logged-out and verification pages; replaces the built-in list",
+                default: "built in",
+            },
+            ConfigField {
+                key: "login_threshold",
+                description: "How far one login score must beat the other before the state is trusted; at least 1",
+                default: "3",
+            },
+        ]
+    }
+
+    async fn run(&self, ctx: &ScrapeContext) -> Result<ScrapeReport> {
+        let iterations: u32 = ctx.setting("iterations", 10)?;
+        let run = JobRun {
+            session: &ctx.session,
+            retry: ctx.retry.policy("jobs.iteration"),
+            output: &ctx.output,
+            ledger: Mutex::new(JobLedger::load(
+                &ctx.setting("ledger", PathBuf::from(DEFAULT_LEDGER))?,
+                ctx.setting("retry_failed", false)?,
+            )?),
+            inspect_listings: ctx.setting("inspect_listings", false)?,
+            emitted: Mutex::new(HashSet::new()),
+            submit_mode: ctx.setting("submit_mode", SubmitMode::default())?,
+            max_form_pages: ctx.setting("max_form_pages", 10)?,
+            visited: Mutex::new(HashSet::new()),
+            credentials: Credentials::load(
+                CREDENTIALS_ENV_PREFIX,
+                &ctx.setting("credentials_file", PathBuf::from(DEFAULT_CREDENTIALS))?,
+            )
+            .map_err(|e| ScrapeError::Config(format!("{:#}", e)))?,
+            answers: AnswerProfile::load(&ctx.setting("answers", PathBuf::from(DEFAULT_ANSWERS))?)
+                .map_err(|e| ScrapeError::Config(format!("{:#}", e)))?,
+            discovery_card_point: ctx.setting("discovery_card_point", None)?,
+            selectors: SelectorCatalog::load(
+                "jobs",
+                DEFAULT_SELECTORS,
+                ctx.setting::<Option<PathBuf>>("selectors", None)?
+                    .as_deref(),
+            )
+            .map_err(|e| ScrapeError::Config(format!("{:#}", e)))?,
+            login: LoginDetector::new(
+                ctx.setting("login_signals", default_login_signals())?,
+                ctx.setting("login_threshold", DEFAULT_LOGIN_THRESHOLD)?,
+            )?,
+        };
+        if run.submit_mode == SubmitMode::DryRun {
+            println!("Dry run: applications stop before the final submit and the ledger is left untouched");
+        }
+        let completed = run.scrape(iterations).await?;
+        Ok(ScrapeReport {
+            summary: format!("{} of {} iterations completed", completed, iterations),
+        })
     }
+}
 
-    pub async fn scrape(&self) -> Result<()> {
-        for iteration in 1..=10 {
-            println!("Starting iteration {} of 10", iteration);
+struct JobRun<'a> {
+    session: &'a BrowserSession,
+    /// Applied to each iteration; between attempts any open modal is dismissed.
+    retry: RetryPolicy,
+    /// Receives every job listing seen, once each.
+    output: &'a Output,
+    ledger: Mutex<JobLedger>,
+    inspect_listings: bool,
+    /// IDs of listings already emitted this run.
+    emitted: Mutex<HashSet<String>>,
+    submit_mode: SubmitMode,
+    max_form_pages: u32,
+    /// IDs of jobs applied to this run, skipped even when the ledger is not
+    /// written.
+    visited: Mutex<HashSet<String>>,
+    answers: AnswerProfile,
+    /// Used to log in when the session is not logged in already.
+    credentials: Option<Credentials>,
+    login: LoginDetector,
+    /// Opt-in fallback for clicking the discovery card by position.
+    discovery_card_point: Option<(i64, i64)>,
+    selectors: SelectorCatalog,
+}
 
-            match self.scrape_single_iteration().await {
-                Ok(_) => println!("Iteration {} completed successfully", iteration),
+impl JobRun<'_> {
+    async fn scrape(&self, iterations: u32) -> Result<u32> {
+        self.session.client().goto(JOBS_HOME).await?;
+        self.wait_for_network_idle().await?;
+        let mut completed = 0;
+        for iteration in 1..=iterations {
+            println!("Starting iteration {} of {}", iteration, iterations);
+
+            let result = self
+                .retry
+                .run_with_hook(
+                    "jobs.iteration",
+                    |_| self.scrape_single_iteration(),
+                    |attempt, _| async move {
+                        self.session
+                            .screenshot(&format!(
+                                "iteration_{}_attempt_{}_error",
+                                iteration, attempt
+                            ))
+                            .await?;
+                        self.handle_unexpected_scenario().await
+                    },
+                )
+                .await;
+            self.session.artifacts().log(&match &result {
+                Ok(Some(job_id)) => format!("Iteration {}: job {}", iteration, job_id),
+                Ok(None) => format!("Iteration {}: no new job to apply to", iteration),
+                Err(e) => format!("Iteration {} failed: {}", iteration, e),
+            })?;
+            match result {
+                Ok(_) => {
+                    completed += 1;
+                    println!("Iteration {} completed successfully", iteration)
+                }
+                // Every later iteration would fail the same way.
+                Err(e @ (ScrapeError::AuthRequired(_) | ScrapeError::ManualActionRequired(_))) => {
+                    return Err(e);
+                }
                 Err(e) => {
                     println!(
                         "Error in iteration {}: {}. Attempting to recover...",
@@ -29,66 +399,499 @@ impl JobScraper {
                 }
             }
 
-            tokio::time::sleep(Duration::from_secs(2)).await;
+            self.wait_for_network_idle().await?;
         }
 
-        Ok(())
+        Ok(completed)
     }
 
-    async fn scrape_single_iteration(&self) -> Result<()> {
-        self.verify_login().await?;
-        tokio::time::sleep(Duration::from_secs(2)).await;
+    /// Searches for a job not yet in the ledger and applies to it,
+    /// returning its ID. Outside dry runs the outcome is recorded in the
+    /// ledger either way.
+    async fn scrape_single_iteration(&self) -> Result<Option<String>> {
+        let wait = || self.session.wait();
+
+        self.ensure_logged_in().await?;
+        self.wait_for_network_idle().await?;
         self.click_discovery_card().await?;
-        tokio::time::sleep(Duration::from_secs(2)).await;
+        wait()
+            .for_visible(self.selectors.get("discovery_carousel"))
+            .await?;
         self.click_first_qualifying_li().await?;
-        tokio::time::sleep(Duration::from_secs(2)).await;
+        wait()
+            .for_clickable(self.selectors.get("all_filters_button"))
+            .await?;
         self.click_all_filters_button().await?;
-        tokio::time::sleep(Duration::from_secs(2)).await;
+        wait()
+            .for_visible(self.selectors.get("filters_panel"))
+            .await?;
         self.select_advanced_filter().await?;
-        tokio::time::sleep(Duration::from_secs(2)).await;
+        wait()
+            .for_clickable(self.selectors.get("show_results_button"))
+            .await?;
         self.click_show_results_button().await?;
-        self.find_clickable_apply_button().await?;
-        tokio::time::sleep(Duration::from_secs(2)).await;
+        wait()
+            .for_visible(self.selectors.get("results_list"))
+            .await?;
+        let mut listings = self.extract_listings().await?;
+        if self.inspect_listings {
+            self.inspect_all(&mut listings).await?;
+        }
+        let selected = self.find_clickable_apply_button().await?;
+        if let Some(id) = &selected {
+            let details = self.read_job_details().await?;
+            match listings.iter_mut().find(|listing| &listing.id == id) {
+                Some(listing) => listing.merge_details(details),
+                None => {
+                    let mut listing = JobListing::new(id.clone());
+                    listing.merge_details(details);
+                    listings.push(listing);
+                }
+            }
+        }
+        self.emit_new_listings(&listings)?;
+
+        let Some(job) = selected.and_then(|id| listings.into_iter().find(|l| l.id == id)) else {
+            return Ok(None);
+        };
+        let job_ref = JobRef {
+            id: job.id.clone(),
+            title: job.title.clone(),
+            company: job.company.clone(),
+            url: job.url.clone(),
+        };
+        self.visited
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(job.id.clone());
+
+        let mut step = "click_apply_button";
+        let result = self.apply(job, &mut step).await;
+        let outcome = match &result {
+            Ok(ApplyResult::Submitted) => JobOutcome::Submitted,
+            Ok(ApplyResult::Declined) => JobOutcome::Declined,
+            Ok(ApplyResult::DryRun) => {
+                println!("Job {}: {}", job_ref.id, ApplyResult::DryRun.description());
+                return result.map(|_| Some(job_ref.id));
+            }
+            // Failed rather than skipped, so `retry_failed` picks the job up
+            // again once e.g. the answers file covers its questions.
+            Ok(other) => JobOutcome::Failed {
+                step: step.to_string(),
+                error: other.description().to_string(),
+            },
+            Err(e) => JobOutcome::Failed {
+                step: step.to_string(),
+                error: e.to_string(),
+            },
+        };
+        println!("Job {}: {:?}", job_ref.id, outcome);
+        if self.submit_mode != SubmitMode::DryRun {
+            let mut ledger = self.ledger.lock().unwrap_or_else(|e| e.into_inner());
+            ledger.record(&job_ref, outcome, Local::now())?;
+            debug_println!("Recorded job {} in {}", job_ref.id, ledger.path().display());
+        }
+
+        result.map(|_| Some(job_ref.id))
+    }
+
+    /// Walks the Easy Apply flow for `job`, capturing every form page, and
+    /// writes the capture to the run's data directory however the walk ends.
+    async fn apply(&self, job: JobListing, step: &mut &'static str) -> Result<ApplyResult> {
+        let mut capture = ApplicationCapture::new(job, self.submit_mode);
+        let result = self.apply_steps(&mut capture, step).await;
+        capture.result = result.as_ref().ok().copied();
+        if let Ok(result) = &result {
+            println!(
+                "Easy Apply for job {} ended in state {:?} after {} pages",
+                capture.job.id,
+                result,
+                capture.states.len()
+            );
+        }
+
+        let artifacts = self.session.artifacts();
+        let path = artifacts.path(
+            ArtifactKind::Data,
+            &format!("application-{}.json", capture.job.id),
+        );
+        // The capture is a record of the walk; failing to save it must not
+        // change how the application is recorded in the ledger.
+        let saved = serde_json::to_string_pretty(&capture)
+            .map_err(anyhow::Error::from)
+            .and_then(|json| Ok(std::fs::write(&path, json)?))
+            .and_then(|()| artifacts.record(ArtifactKind::Data, &path, "application"));
+        match saved {
+            Ok(()) => debug_println!("Application capture saved as {}", path.display()),
+            Err(e) => println!("Could not save the application capture: {:#}", e),
+        }
+        result
+    }
+
+    /// The Easy Apply steps themselves, keeping `step` at the step being run
+    /// so a failure can be attributed.
+    async fn apply_steps(
+        &self,
+        capture: &mut ApplicationCapture,
+        step: &mut &'static str,
+    ) -> Result<ApplyResult> {
+        self.wait_for_network_idle().await?;
         self.click_apply_button().await?;
-        tokio::time::sleep(Duration::from_secs(2)).await;
-        self.click_modal_primary_button().await?;
-        tokio::time::sleep(Duration::from_secs(2)).await;
-        self.click_next_button_in_modal().await?;
-        tokio::time::sleep(Duration::from_secs(2)).await;
-        self.handle_review_and_submit().await?;
-        tokio::time::sleep(Duration::from_secs(2)).await;
-        let should_continue = self.handle_sponsorship_question().await?;
-        if !should_continue {
-            println!("Application process complete or sponsorship question not found.");
-            self.take_screenshot("process_complete_screenshot.png")
+        self.session
+            .wait()
+            .for_visible(self.selectors.get("modal_footer"))
+            .await?;
+        let result = self.walk_form(capture, step).await?;
+        match result {
+            ApplyResult::Submitted => self.after_submit(capture).await,
+            ApplyResult::Closed => {}
+            _ => {
+                *step = "dismiss_modal";
+                self.handle_unexpected_scenario().await?;
+            }
+        }
+        Ok(result)
+    }
+
+    /// Captures the page shown after submitting and closes the modal. The
+    /// application is already in, so failures are logged and the outcome
+    /// stays `Submitted`.
+    async fn after_submit(&self, capture: &mut ApplicationCapture) {
+        if let Err(e) = self.wait_for_network_idle().await {
+            println!("Could not wait for the submission to settle: {}", e);
+        }
+        match self.detect_modal_page().await {
+            Ok(state) => {
+                println!("Easy Apply page after submitting: {:?}", state.page);
+                capture.states.push(state);
+            }
+            Err(e) => println!("Could not read the page after submitting: {}", e),
+        }
+        if let Err(e) = self.session.screenshot("process_complete").await {
+            println!("Could not take the post-submit screenshot: {}", e);
+        }
+        if let Err(e) = self.session.log_current_url().await {
+            println!("Could not log the URL after submitting: {}", e);
+        }
+        if let Err(e) = self.session.log_page_title().await {
+            println!("Could not log the page title after submitting: {}", e);
+        }
+        if let Err(e) = self.handle_unexpected_scenario().await {
+            println!("Could not close the modal after submitting: {}", e);
+        }
+    }
+
+    /// Drives the modal page by page: form pages are filled and advanced,
+    /// the review page is submitted as `submit_mode` allows, and the walk
+    /// stops at the first terminal state or after `max_form_pages` pages.
+    async fn walk_form(
+        &self,
+        capture: &mut ApplicationCapture,
+        step: &mut &'static str,
+    ) -> Result<ApplyResult> {
+        for _ in 0..self.max_form_pages {
+            *step = "detect_modal_page";
+            let state = self.detect_modal_page().await?;
+            println!(
+                "Easy Apply page: {:?}{}",
+                state.page,
+                state
+                    .heading
+                    .as_deref()
+                    .map(|h| format!(" ({})", h))
+                    .unwrap_or_default()
+            );
+            for error in &state.errors {
+                println!("  Form error: {}", error);
+            }
+            let stuck = capture
+                .states
+                .last()
+                .is_some_and(|previous| previous.same_page(&state));
+            capture.states.push(state.clone());
+
+            match state.page {
+                ModalPage::Submitted => return Ok(ApplyResult::Submitted),
+                ModalPage::Closed => return Ok(ApplyResult::Closed),
+                ModalPage::Error => return Ok(ApplyResult::Error),
+                _ if stuck => return Ok(ApplyResult::Stuck),
+                ModalPage::Review => {
+                    *step = "submit_application";
+                    // Once the submit click went through the application is
+                    // in, whatever the pages after it do.
+                    return Ok(self
+                        .submit_application(capture)
+                        .await?
+                        .unwrap_or(ApplyResult::Submitted));
+                }
+                ModalPage::ContactInfo
+                | ModalPage::Resume
+                | ModalPage::Questions
+                | ModalPage::Unknown => {
+                    *step = "fill_form_page";
+                    if !self.fill_form_page(capture).await? {
+                        return Ok(ApplyResult::Unanswered);
+                    }
+                    if state.primary_button.is_none() {
+                        return Ok(ApplyResult::Stuck);
+                    }
+                    *step = "click_modal_primary_button";
+                    self.click_modal_primary_button().await?;
+                }
+            }
+            self.wait_for_network_idle().await?;
+        }
+        Ok(ApplyResult::PageLimit)
+    }
+
+    /// Works out which page the Easy Apply modal is showing.
+    async fn detect_modal_page(&self) -> Result<ModalState> {
+        let script = r#"
+            const sel = arguments[0];
+            const clean = text => text ? text.replace(/\s+/g, ' ').trim() : null;
+            const modal = pick(sel.modal);
+            if (!modal) {
+                return { page: 'closed', heading: null, progress: null, primary_button: null, errors: [] };
+            }
+
+            const heading = clean((pick(sel.modal_heading, modal) || {}).textContent);
+            const buttons = pickAll(sel.modal_primary_button, modal);
+            const primaryButton = buttons.length ? clean(buttons[buttons.length - 1].textContent) : null;
+            const progressBar = pick(sel.modal_progress, modal);
+            const progressValue = progressBar
+                ? Number(progressBar.getAttribute('value') || progressBar.getAttribute('aria-valuenow'))
+                : NaN;
+            const errors = pickAll(sel.modal_errors, modal)
+                .map(el => clean(el.textContent))
+                .filter(Boolean);
+
+            const text = clean(modal.textContent).toLowerCase();
+            const title = (heading || '').toLowerCase();
+            const hasInputs = !!pick(sel.modal_inputs, modal);
+            let page;
+            if (/application (was )?sent|application submitted|your application was submitted/.test(text)) {
+                page = 'submitted';
+            } else if (primaryButton && primaryButton.toLowerCase() === 'submit application') {
+                page = 'review';
+            } else if (/contact info/.test(title)) {
+                page = 'contact_info';
+            } else if (/resume|cv/.test(title) || pick(sel.resume_upload, modal)) {
+                page = 'resume';
+            } else if (hasInputs) {
+                page = 'questions';
+            } else if (/something went wrong|try again later|no longer accepting applications/.test(text)) {
+                page = 'error';
+            } else {
+                page = 'unknown';
+            }
+
+            return {
+                page,
+                heading,
+                progress: Number.isFinite(progressValue) ? Math.round(progressValue) : null,
+                primary_button: primaryButton,
+                errors,
+            };
+        "#;
+        let selectors = self.selectors.json(&[
+            "modal",
+            "modal_heading",
+            "modal_primary_button",
+            "modal_progress",
+            "modal_errors",
+            "modal_inputs",
+            "resume_upload",
+        ]);
+        self.session
+            .execute_with_args(script, vec![selectors])
+            .await
+    }
+
+    /// The selector chains `modalForm` needs.
+    fn modal_form_selectors(&self) -> serde_json::Value {
+        self.selectors.json(&["modal", "modal_heading"])
+    }
+
+    /// Lists the open modal page's questions and their current values.
+    async fn read_form_page(&self) -> Result<Option<FormPage>> {
+        let script = format!(
+            "{}\n{}",
+            MODAL_FORM,
+            r#"
+            const form = modalForm(arguments[0]);
+            return form && {
+                heading: form.heading,
+                fields: form.questions.map(({ elements, ...field }) => field),
+            };
+            "#
+        );
+        self.session
+            .execute_with_args(&script, vec![self.modal_form_selectors()])
+            .await
+    }
+
+    /// Answers what the answer profile covers on the open modal page and
+    /// captures the result. Questions left empty are reported rather than
+    /// guessed; returns false if any of them is required.
+    async fn fill_form_page(&self, capture: &mut ApplicationCapture) -> Result<bool> {
+        let Some(page) = self.read_form_page().await? else {
+            debug_println!("No modal open to fill");
+            return Ok(true);
+        };
+
+        let mut answers = Vec::new();
+        let mut unanswered = Vec::new();
+        for (index, field) in page.fields.iter().enumerate() {
+            if QuestionKind::of(field).is_none() {
+                continue;
+            }
+            match self.answers.answer(field) {
+                Some(answer) => answers.push(json!({ "index": index, "answer": answer })),
+                None if field.is_empty() => {
+                    unanswered.push((index, "no matching answer".to_string()))
+                }
+                None => {}
+            }
+        }
+
+        if !answers.is_empty() {
+            let script = format!(
+                "{}\n{}",
+                MODAL_FORM,
+                r#"
+                const form = modalForm(arguments[1]);
+                if (!form) return [];
+                const same = (a, b) => a != null && a.trim().toLowerCase() === b.trim().toLowerCase();
+                const setValue = (el, value) => {
+                    const prototype = Object.getPrototypeOf(el);
+                    Object.getOwnPropertyDescriptor(prototype, 'value').set.call(el, value);
+                    el.dispatchEvent(new Event('input', { bubbles: true }));
+                    el.dispatchEvent(new Event('change', { bubbles: true }));
+                };
+
+                return arguments[0].map(({ index, answer }) => {
+                    const question = form.questions[index];
+                    if (!question) return { index, filled: false, detail: 'question left the page' };
+                    const [el] = question.elements;
+                    switch (question.kind) {
+                        case 'radio': {
+                            const position = question.options.findIndex(option => same(option, answer));
+                            if (position < 0) return { index, filled: false, detail: `'${answer}' is not one of the options` };
+                            question.elements[position].click();
+                            break;
+                        }
+                        case 'select': {
+                            const option = Array.from(el.options).find(o => same(o.textContent, answer) || same(o.value, answer));
+                            if (!option) return { index, filled: false, detail: `'${answer}' is not one of the options` };
+                            setValue(el, option.value);
+                            break;
+                        }
+                        case 'checkbox':
+                            if (el.checked !== ['yes', 'true', 'checked'].includes(answer.trim().toLowerCase())) el.click();
+                            break;
+                        default:
+                            setValue(el, answer);
+                    }
+                    return { index, filled: true, detail: null };
+                });
+                "#
+            );
+            let results: Vec<FillResult> = self
+                .session
+                .execute_with_args(&script, vec![answers.into(), self.modal_form_selectors()])
                 .await?;
-            return Ok(());
+            for result in results.into_iter().filter(|result| !result.filled) {
+                let reason = result
+                    .detail
+                    .unwrap_or_else(|| "could not be filled".to_string());
+                unanswered.push((result.index, reason));
+            }
         }
-        tokio::time::sleep(Duration::from_secs(2)).await;
-        self.take_screenshot("post_filters_click_screenshot.png")
+
+        let page_number = capture.pages.len() as u32 + 1;
+        let mut complete = true;
+        for (index, reason) in unanswered {
+            let field = &page.fields[index];
+            println!(
+                "Unanswered{} question on page {}: {} ({})",
+                if field.required { " required" } else { "" },
+                page_number,
+                field.label.as_deref().unwrap_or("(unlabelled)"),
+                reason
+            );
+            complete &= !field.required;
+            capture.unanswered.push(UnansweredQuestion {
+                page: page_number,
+                label: field.label.clone(),
+                kind: field.kind.clone(),
+                required: field.required,
+                options: field.options.clone(),
+                reason,
+            });
+        }
+
+        self.capture_form_page(capture).await?;
+        Ok(complete)
+    }
+
+    /// Records the open modal page's inputs and their values, with a screenshot.
+    async fn capture_form_page(&self, capture: &mut ApplicationCapture) -> Result<()> {
+        let page = self.read_form_page().await?;
+        let Some(page) = page else {
+            debug_println!("No modal open to capture");
+            return Ok(());
+        };
+        capture.push_page(page);
+        self.session
+            .screenshot(&format!(
+                "application_{}_page_{}",
+                capture.job.id,
+                capture.pages.len()
+            ))
             .await?;
-        self.print_current_url().await?;
-        self.print_page_title().await?;
         Ok(())
     }
 
+    /// Shows the job and its filled form, and asks whether to submit.
+    async fn confirm_submit(&self, capture: &ApplicationCapture) -> Result<bool> {
+        println!("{}", capture.summary());
+        let answer = tokio::task::spawn_blocking(|| -> std::io::Result<String> {
+            print!("Submit this application? [y/N]: ");
+            std::io::stdout().flush()?;
+            let mut answer = String::new();
+            std::io::stdin().read_line(&mut answer)?;
+            Ok(answer)
+        })
+        .await
+        .map_err(anyhow::Error::from)??;
+        Ok(matches!(
+            answer.trim().to_ascii_lowercase().as_str(),
+            "y" | "yes"
+        ))
+    }
+
+    /// Lets the page settle after actions that have no single element to
+    /// wait for. Pages that keep polling never go idle, so a timeout is
+    /// logged and the run carries on; the next step's own wait decides
+    /// whether the page is usable.
+    async fn wait_for_network_idle(&self) -> Result<()> {
+        match self.session.wait().for_network_idle(NETWORK_QUIET).await {
+            Err(ScrapeError::NavigationTimeout(e)) => {
+                println!("Network did not go idle ({}); continuing", e);
+                Ok(())
+            }
+            other => other,
+        }
+    }
+
     async fn handle_unexpected_scenario(&self) -> Result<()> {
         let dismiss_script = r#"
-        function findAndClickDismissButton() {
-            const selectors = [
-                'button[aria-label="Dismiss"][data-test-modal-close-btn]',
-                'button[aria-label="Dismiss"]',
-                'button.artdeco-modal__dismiss',
-                'button.artdeco-button--circle[aria-label="Dismiss"]',
-                'button.artdeco-button--circle.artdeco-button--muted',
-                'button[data-test-modal-close-btn]'
-            ];
-    
+        function findAndClickDismissButton(selectors) {
             for (const selector of selectors) {
                 const button = document.querySelector(selector);
                 if (button) {
                     button.click();
-                    return `Dismiss button clicked using selector: ${selector}`;
+                    return ok('dismissed', `selector: ${selector}`);
                 }
             }
     
@@ -97,568 +900,597 @@ impl JobScraper {
             for (const button of buttons) {
                 if (button.textContent.trim().toLowerCase() === 'dismiss') {
                     button.click();
-                    return 'Dismiss button clicked by text content';
+                    return ok('dismissed', 'by text content');
                 }
             }
     
-            return "Dismiss button not found";
+            return skipped('no_dismiss_button');
         }
     
-        return findAndClickDismissButton();
+        return findAndClickDismissButton(arguments[0]);
         "#;
 
-        let result: Value = self.client.execute(dismiss_script, vec![]).await?;
-        println!("Dismiss button result: {:?}", result);
-
-        tokio::time::sleep(Duration::from_secs(2)).await;
+        let outcome = self
+            .session
+            .run_step_with_args(
+                "dismiss_modal",
+                dismiss_script,
+                vec![json!(self.selectors.get("modal_dismiss"))],
+            )
+            .await?;
+        println!("Dismiss button result: {}", outcome);
+
+        // The "discard application?" confirmation only follows a half-finished application.
+        match self
+            .session
+            .wait()
+            .timeout(Duration::from_secs(3))
+            .for_visible(self.selectors.get("discard_confirm"))
+            .await
+        {
+            Ok(()) | Err(ScrapeError::NavigationTimeout(_)) => {}
+            Err(e) => return Err(e),
+        }
 
         let close_incomplete_script = r#"
-            const closeButton = document.querySelector('button.artdeco-modal__confirm-dialog-btn');
+            const closeButton = pick(arguments[0]);
             if (closeButton) {
                 closeButton.click();
-                return "Close button clicked";
+                return ok('closed');
             }
-            return "Close button not found";
+            return skipped('no_close_button');
         "#;
 
-        let result: Value = self.client.execute(close_incomplete_script, vec![]).await?;
-        println!("Close button result: {:?}", result);
+        let outcome = self
+            .session
+            .run_step_with_args(
+                "discard_application",
+                close_incomplete_script,
+                vec![json!(self.selectors.get("discard_confirm"))],
+            )
+            .await?;
+        println!("Close button result: {}", outcome);
 
         Ok(())
     }
 
     async fn click_modal_primary_button(&self) -> Result<()> {
         let script = r#"
-            function clickModalPrimaryButton() {
-                const modal = document.querySelector('div.artdeco-modal');
+            function clickModalPrimaryButton(sel) {
+                const modal = pick(sel.modal);
                 if (!modal) {
-                    return "Modal not found";
-                }
-                
-                const footer = modal.querySelector('footer');
-                if (!footer) {
-                    return "Modal footer not found";
+                    return failed('not_found', 'modal');
                 }
 
-                const primaryButton = footer.querySelector('button.artdeco-button--primary');
+                // The last one, the same button the page detector read.
+                const buttons = pickAll(sel.modal_primary_button, modal);
+                const primaryButton = buttons[buttons.length - 1];
                 if (!primaryButton) {
-                    return "Primary button not found in modal footer";
+                    return failed('not_found', 'primary button in modal footer');
                 }
-                
+
                 primaryButton.click();
-                return "Successfully clicked the primary button in modal footer";
+                return ok('clicked');
             }
-            return clickModalPrimaryButton();
+            return clickModalPrimaryButton(arguments[0]);
         "#;
 
-        let result: Value = self.client.execute(script, vec![]).await?;
-
-        // async fn take_and_save_screenshot(client: &WebDriver, filename: &str) -> Result<()> {
-        //     let screenshot = client.screenshot(ScreenshotOptions::default()).await?;
-        //     std::fs::write(filename, &screenshot)?;
-        //     println!("Screenshot saved as {}", filename);
-        //     Ok(())
-        // }
+        self.session
+            .run_step_with_args(
+                "click_modal_primary_button",
+                script,
+                vec![self.selectors.json(&["modal", "modal_primary_button"])],
+            )
+            .await?;
+        println!("Successfully clicked the primary button in modal footer");
+        Ok(())
+    }
 
-        match result.as_str() {
-            Some("Successfully clicked the primary button in modal footer") => {
-                println!("Successfully clicked the primary button in modal footer");
-                // take_and_save_screenshot(&self.client, "success_screenshot.png").await?;
-                Ok(())
-            }
-            Some(error_message) => {
-                println!("Failed to click primary button: {}", error_message);
-                // take_and_save_screenshot(&self.client, "error_screenshot.png").await?;
-                Err(anyhow!("Failed to click primary button: {}", error_message))
-            }
-            None => {
-                println!("Unexpected result from JavaScript execution");
-                // take_and_save_screenshot(&self.client, "unexpected_screenshot.png").await?;
-                Err(anyhow!("Unexpected result from JavaScript execution"))
+    /// Logs in with the configured credentials if the session is logged
+    /// out. Checkpoint and two-step verification pages end the run with
+    /// [`ScrapeError::ManualActionRequired`] rather than being guessed at,
+    /// and pages that cannot be told apart fail the iteration.
+    async fn ensure_logged_in(&self) -> Result<()> {
+        match self.login_state().await? {
+            LoginState::LoggedIn => return Ok(()),
+            LoginState::LoggedOut => {}
+            LoginState::Unknown => {
+                self.session.screenshot("login_state_unknown").await?;
+                return Err(ScrapeError::UnexpectedPage(
+                    "cannot tell whether the session is logged in; adjust login_signals if the site changed".to_string(),
+                ));
             }
         }
+        let Some(credentials) = &self.credentials else {
+            return Err(ScrapeError::AuthRequired(format!(
+                "not logged in and no credentials configured; set {0}_USERNAME and {0}_PASSWORD or a credentials_file",
+                CREDENTIALS_ENV_PREFIX
+            )));
+        };
+
+        println!("Not logged in; logging in as {}", credentials.username);
+        self.login(credentials).await?;
+        if self.login_state().await? != LoginState::LoggedIn {
+            self.session.screenshot("login_failed").await?;
+            return Err(ScrapeError::AuthRequired(
+                "still not logged in after submitting credentials".to_string(),
+            ));
+        }
+        self.session
+            .artifacts()
+            .log(&format!("Logged in as {}", credentials.username))?;
+        // The search steps start from the jobs home page.
+        self.session.client().goto(JOBS_HOME).await?;
+        self.wait_for_network_idle().await
+    }
+
+    /// Detects the login state of the current page, logging the signals
+    /// that decided it. A verification page is an error.
+    async fn login_state(&self) -> Result<LoginState> {
+        let check = self.login.detect(self.session).await?;
+        debug_println!("Login state: {}", check);
+        self.session
+            .artifacts()
+            .log(&format!("Login state: {}", check))?;
+        if check.challenge {
+            self.session.screenshot("login_checkpoint").await?;
+            let url = self.session.client().current_url().await?;
+            return Err(ScrapeError::ManualActionRequired(format!(
+                "complete the verification at {} in the browser, then run again",
+                url
+            )));
+        }
+        Ok(check.state)
     }
 
-    async fn login(&self, username: &str, password: &str) -> Result<()> {
-        self.client.goto("https://www.linkedin.com/jobs").await?;
+    async fn login(&self, credentials: &Credentials) -> Result<()> {
+        self.session
+            .client()
+            .goto("https://www.linkedin.com/login")
+            .await?;
+        let login_url = self.session.client().current_url().await?;
         let username_field = self
-            .client
-            .find(Locator::Css("input[name='session_key']"))
+            .session
+            .find(self.selectors.get("login_username"))
             .await?;
-        username_field.send_keys(username).await?;
+        username_field.send_keys(&credentials.username).await?;
         let password_field = self
-            .client
-            .find(Locator::Css("input[name='session_password']"))
+            .session
+            .find(self.selectors.get("login_password"))
             .await?;
-        password_field.send_keys(password).await?;
+        password_field.send_keys(&credentials.password).await?;
         let submit_button = self
-            .client
-            .find(Locator::Css("button[type='submit']"))
+            .session
+            .find(self.selectors.get("login_submit"))
             .await?;
         submit_button.click().await?;
-        tokio::time::sleep(Duration::from_secs(2)).await;
-        Ok(())
-    }
-
-    async fn verify_login(&self) -> Result<bool> {
-        let (profile, url, form, message) = try_join!(
-            self.check_user_profile(),
-            self.check_url(),
-            self.check_login_form_absence(),
-            self.check_welcome_message()
-        )?;
 
-        if profile || url || form || message {
-            println!("Login successful!");
-            Ok(true)
-        } else {
-            Ok(false)
+        // A rejected password reloads the login page rather than leaving it.
+        match self.session.wait().for_url_change(login_url.as_str()).await {
+            Ok(_) | Err(ScrapeError::NavigationTimeout(_)) => {}
+            Err(e) => return Err(e),
         }
+        self.wait_for_network_idle().await?;
+        self.check_login_result().await
     }
 
-    async fn check_user_profile(&self) -> Result<bool> {
-        Ok(self
-            .client
-            .find(Locator::Css(".user-profile"))
-            .await
-            .is_ok())
-    }
-
-    async fn check_url(&self) -> Result<bool> {
-        let current_url = self.client.current_url().await?;
-        Ok(current_url
-            .as_ref()
-            .starts_with("https://www.linkedin.com/feed/"))
-    }
-
-    async fn check_login_form_absence(&self) -> Result<bool> {
-        Ok(self.client.find(Locator::Css("form#login")).await.is_err())
-    }
+    /// Reports the error message of a rejected login. Verification pages
+    /// are left to [`Self::login_state`].
+    async fn check_login_result(&self) -> Result<()> {
+        let script = r#"
+            const url = window.location.href;
+            const error = pick(arguments[0]);
+            if (error && error.textContent.trim()) {
+                return failed('auth_required', `login rejected: ${error.textContent.trim()}`);
+            }
+            return ok('submitted', url);
+        "#;
 
-    async fn check_welcome_message(&self) -> Result<bool> {
-        let body_text = self.client.find(Locator::Css("body")).await?.text().await?;
-        Ok(body_text.contains("Welcome") || body_text.contains("Dashboard"))
+        self.session
+            .run_step_with_args(
+                "check_login_result",
+                script,
+                vec![json!(self.selectors.get("login_error"))],
+            )
+            .await?;
+        Ok(())
     }
 
     async fn click_discovery_card(&self) -> Result<()> {
-        self.click_element(".discovery-templates-jump-back-in-card")
-            .await
+        let mut target = ClickTarget::css(self.selectors.get("discovery_card"));
+        if let Some((x, y)) = self.discovery_card_point {
+            target = target.or_at_point(x, y);
+        }
+        self.click_element(&target).await
     }
 
-    async fn click_element(&self, fallback_selector: &str) -> Result<()> {
-        let (x, y) = (814, 745);
-        let js_code = format!(
-            r#"
-            function clickAt(x, y) {{
-                const element = document.elementFromPoint(x, y);
-                if (element) {{
-                    const clickEvent = new MouseEvent('click', {{
-                        view: window,
-                        bubbles: true,
-                        cancelable: true,
-                        clientX: x,
-                        clientY: y
-                    }});
-                    element.dispatchEvent(clickEvent);
-                    return 'coordinate_click';
-                }}
-                const fallbackElement = document.querySelector('{}');
-                if (fallbackElement) {{
-                    fallbackElement.click();
-                    return 'selector_click';
-                }}
-                return 'click_fail';
-            }}
-            return clickAt({}, {});
-            "#,
-            fallback_selector, x, y
-        );
-
-        let before_screenshot = self.client.screenshot().await?;
-        std::fs::write("before_click_screenshot.png", &before_screenshot)?;
-        println!("Before-click screenshot saved as before_click_screenshot.png");
+    async fn click_element(&self, target: &ClickTarget) -> Result<()> {
+        self.session.screenshot("before_click").await?;
 
-        let result: Value = self.client.execute(&js_code, vec![]).await?;
-
-        match result.as_str() {
-            Some("coordinate_click") => println!("Clicked using coordinates ({}, {})", x, y),
-            Some("selector_click") => {
-                println!("Clicked using fallback selector: {}", fallback_selector)
-            }
-            _ => return Err(anyhow!("Failed to click element")),
+        match self.session.click(target).await? {
+            ClickMethod::Native => println!("Clicked {}", target),
+            ClickMethod::Pointer => println!("Clicked {} with the pointer", target),
+            ClickMethod::Point(x, y) => println!("Clicked at ({}, {}) for {}", x, y, target),
         }
 
-        tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+        self.wait_for_network_idle().await?;
 
-        let after_screenshot = self.client.screenshot().await?;
-        std::fs::write("after_click_screenshot.png", &after_screenshot)?;
-        println!("After-click screenshot saved as after_click_screenshot.png");
+        self.session.screenshot("after_click").await?;
 
         Ok(())
     }
 
     async fn click_first_qualifying_li(&self) -> Result<()> {
         let script = r#"
-            const ul = document.querySelector("ul.artdeco-carousel__slider");
-            if (!ul) return "UL not found";
-            
-            const lis = ul.querySelectorAll("li.artdeco-carousel__item");
-            for (const li of lis) {
-                const innerLi = li.querySelector("li.discovery-templates-jump-back-in-card");
-                if (innerLi) {
-                    const link = innerLi.querySelector("a.app-aware-link");
-                    if (link) {
-                        link.click();
-                        return "Clicked qualifying LI";
-                    }
-                }
-            }
-            return "No qualifying LI found";
+            const sel = arguments[0];
+            const carousel = pick(sel.discovery_carousel);
+            if (!carousel) return failed('not_found', 'discovery carousel');
+
+            const link = pick(sel.discovery_card_link, carousel);
+            if (!link) return failed('not_found', 'qualifying li element');
+            link.click();
+            return ok('clicked');
         "#;
 
-        let result: Value = self.client.execute(script, vec![]).await?;
-
-        match result.as_str() {
-            Some("Clicked qualifying LI") => {
-                println!("Successfully clicked on the first qualifying li element");
-                Ok(())
-            }
-            Some("No qualifying LI found") => Err(anyhow!("No qualifying li element found")),
-            Some("UL not found") => Err(anyhow!(
-                "UL with class 'artdeco-carousel__slider' not found"
-            )),
-            _ => Err(anyhow!("Unexpected result when clicking li element")),
-        }
+        self.session
+            .run_step_with_args(
+                "click_first_qualifying_li",
+                script,
+                vec![self
+                    .selectors
+                    .json(&["discovery_carousel", "discovery_card_link"])],
+            )
+            .await?;
+        println!("Successfully clicked on the first qualifying li element");
+        Ok(())
     }
 
     async fn click_all_filters_button(&self) -> Result<()> {
-        let script = r#"
-            const buttons = Array.from(document.querySelectorAll('button'));
-            const allFiltersButton = buttons.find(button => button.textContent.trim() === 'All filters');
-            if (allFiltersButton) {
-                allFiltersButton.click();
-                return true;
-            }
-            return false;
-        "#;
-
-        let result: Value = self.client.execute(script, vec![]).await?;
-
-        match result.as_bool() {
-            Some(true) => {
-                println!("Successfully clicked 'All filters' button");
-                Ok(())
-            }
-            _ => Err(anyhow!("Failed to find or click 'All filters' button")),
-        }
+        self.session
+            .click(&ClickTarget::css(self.selectors.get("all_filters_button")))
+            .await?;
+        println!("Successfully clicked 'All filters' button");
+        Ok(())
     }
 
     async fn select_advanced_filter(&self) -> Result<()> {
         let script = r#"
-            const container = document.querySelector('.search-reusables__secondary-filters-filter');
-            if (!container) return "Container not found";
+            const sel = arguments[0];
+            const container = pick(sel.filters_panel);
+            if (!container) return failed('not_found', 'filter container');
             
-            const radioInput = container.querySelector('input[id="advanced-filter-sortBy-DD"]');
-            if (!radioInput) return "Radio input not found";
+            const radioInput = pick(sel.sort_by_recent, container);
+            if (!radioInput) return failed('not_found', 'sort-by radio input');
             
             radioInput.click();
-            return "Successfully clicked the advanced filter radio input";
+            return ok('selected');
         "#;
 
-        let result: Value = self.client.execute(script, vec![]).await?;
-
-        match result.as_str() {
-            Some("Successfully clicked the advanced filter radio input") => {
-                println!("Successfully selected the advanced filter");
-                Ok(())
-            }
-            Some(error_message) => Err(anyhow!(
-                "Failed to select advanced filter: {}",
-                error_message
-            )),
-            None => Err(anyhow!("Unexpected result from JavaScript execution")),
-        }
+        self.session
+            .run_step_with_args(
+                "select_advanced_filter",
+                script,
+                vec![self.selectors.json(&["filters_panel", "sort_by_recent"])],
+            )
+            .await?;
+        println!("Successfully selected the advanced filter");
+        Ok(())
     }
 
     async fn click_show_results_button(&self) -> Result<()> {
-        let script = r#"
-            const actionbar = document.querySelector('.artdeco-modal__actionbar');
-            if (!actionbar) return "Actionbar not found";
-            
-            const showResultsButton = actionbar.querySelector('button.search-reusables__secondary-filters-show-results-button');
-            if (!showResultsButton) return "Show results button not found";
-            
-            showResultsButton.click();
-            return "Successfully clicked the show results button";
-        "#;
-
-        let result: Value = self.client.execute(script, vec![]).await?;
-
-        match result.as_str() {
-            Some("Successfully clicked the show results button") => {
-                println!("Successfully clicked the show results button");
-                Ok(())
-            }
-            Some(error_message) => Err(anyhow!(
-                "Failed to click show results button: {}",
-                error_message
-            )),
-            None => Err(anyhow!("Unexpected result from JavaScript execution")),
-        }
+        self.session
+            .click(&ClickTarget::css(self.selectors.get("show_results_button")))
+            .await?;
+        println!("Successfully clicked the show results button");
+        Ok(())
     }
 
-    async fn find_clickable_apply_button(&self) -> Result<()> {
-        let script = r#"
-        async function findClickableApplyButton() {
+    /// Selects the first job in the results with a clickable apply button
+    /// that neither the ledger nor this run has seen, returning its ID.
+    async fn find_clickable_apply_button(&self) -> Result<Option<String>> {
+        let script = format!(
+            "{}\n{}",
+            JOB_ID_OF,
+            r#"
+        async function findClickableApplyButton(handled, sel) {
             const isApplyButtonClickable = () => {
-                const applyButton = document.querySelector('.job-details-jobs-unified-top-card__container--two-pane button.jobs-apply-button');
+                const details = pick(sel.job_details);
+                const applyButton = details && pick(sel.apply_button, details);
                 return applyButton && !applyButton.disabled;
             };
+            const currentJobId = () => new URLSearchParams(window.location.search).get('currentJobId');
 
-            if (isApplyButtonClickable()) {
-                return "Apply button is already clickable";
+            const selected = currentJobId();
+            if (selected && !handled.includes(selected) && isApplyButtonClickable()) {
+                return ok('already_clickable', selected);
             }
 
-            const jobList = document.querySelector('ul.scaffold-layout__list-container');
+            const jobList = pick(sel.results_list);
             if (!jobList) {
-                return "Job list not found";
+                return failed('not_found', 'job list container');
             }
 
-            const jobItems = jobList.querySelectorAll('li');
+            const jobItems = pickAll(sel.result_item, jobList);
             for (let item of jobItems) {
+                const id = jobIdOf(item, sel.job_id_element);
+                if (!id || handled.includes(id)) continue;
                 item.click();
-                await new Promise(resolve => setTimeout(resolve, 1000)); // Wait for 1 second
-                if (isApplyButtonClickable()) {
-                    return "Found clickable apply button";
+                if (await waitFor(() => currentJobId() === id && isApplyButtonClickable(), 2000)) {
+                    return ok('found', id);
                 }
             }
 
-            return "No clickable apply button found";
+            return skipped('none_clickable');
         }
-        return findClickableApplyButton();
-        "#;
-
-        let result: Value = self.client.execute(script, vec![]).await?;
+        return findClickableApplyButton(arguments[0], arguments[1]);
+        "#
+        );
 
-        match result.as_str() {
-            Some("Apply button is already clickable") | Some("Found clickable apply button") => {
-                println!("Apply button is clickable");
-                Ok(())
+        let mut handled = self
+            .ledger
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .handled_ids();
+        handled.extend(
+            self.visited
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .iter()
+                .cloned(),
+        );
+        let outcome = self
+            .session
+            .run_step_with_args(
+                "find_clickable_apply_button",
+                &script,
+                vec![
+                    handled.into(),
+                    self.selectors.json(&[
+                        "job_details",
+                        "apply_button",
+                        "results_list",
+                        "result_item",
+                        "job_id_element",
+                    ]),
+                ],
+            )
+            .await?;
+        match (&outcome, outcome.detail()) {
+            (StepOutcome::Ok { .. }, Some(id)) => {
+                println!("Apply button is clickable for job {}", id);
+                Ok(Some(id.to_string()))
             }
-            Some("No clickable apply button found") => {
-                println!("No job with a clickable apply button found. You may need to load more results or adjust your search.");
-                Ok(())
+            _ => {
+                println!("No new job with a clickable apply button found. You may need to load more results or adjust your search.");
+                Ok(None)
             }
-            Some("Job list not found") => Err(anyhow!("Could not find the job list container")),
-            Some(error_message) => Err(anyhow!("Error: {}", error_message)),
-            None => Err(anyhow!("Unexpected result from JavaScript execution")),
         }
     }
 
-    async fn click_apply_button(&self) -> Result<()> {
-        let script = r#"
-            function clickApplyButton() {
-                const container = document.querySelector('.job-details-jobs-unified-top-card__container--two-pane');
-                if (!container) {
-                    return "Job details container not found";
-                }
-                
-                const applyButton = container.querySelector('button.jobs-apply-button');
-                if (!applyButton) {
-                    return "Apply button not found";
-                }
-                
-                applyButton.click();
-                return "Successfully clicked the apply button";
-            }
-            return clickApplyButton();
-        "#;
-
-        let result: Value = self.client.execute(script, vec![]).await?;
+    /// Reads every card in the results list.
+    async fn extract_listings(&self) -> Result<Vec<JobListing>> {
+        let script = format!(
+            "{}\n{}",
+            JOB_ID_OF,
+            r#"
+            const sel = arguments[0];
+            const text = (root, chain) => {
+                const el = pick(chain, root);
+                return el ? el.textContent : null;
+            };
+            const list = pick(sel.results_list);
+            const items = list ? pickAll(sel.result_item, list) : [];
+            const seen = new Set();
+            return items.flatMap(item => {
+                const id = jobIdOf(item, sel.job_id_element);
+                if (!id || seen.has(id)) return [];
+                seen.add(id);
+                const time = pick(sel.card_posted_time, item);
+                return [{
+                    id,
+                    title: text(item, sel.card_title),
+                    company: text(item, sel.card_company),
+                    location: text(item, sel.card_location),
+                    posted_datetime: time ? time.getAttribute('datetime') : null,
+                    posted: time ? time.textContent : null,
+                    easy_apply: item.textContent.includes('Easy Apply'),
+                }];
+            });
+        "#
+        );
 
-        match result.as_str() {
-            Some("Successfully clicked the apply button") => {
-                println!("Successfully clicked the apply button");
-                Ok(())
-            }
-            Some(error_message) => Err(anyhow!("Failed to click apply button: {}", error_message)),
-            None => Err(anyhow!("Unexpected result from JavaScript execution")),
-        }
+        let selectors = self.selectors.json(&[
+            "results_list",
+            "result_item",
+            "job_id_element",
+            "card_title",
+            "card_company",
+            "card_location",
+            "card_posted_time",
+        ]);
+        let cards: Vec<RawJobCard> = self
+            .session
+            .execute_with_args(&script, vec![selectors])
+            .await?;
+        let listings: Vec<JobListing> = cards
+            .into_iter()
+            .filter_map(JobListing::from_card)
+            .collect();
+        println!("Found {} job listings", listings.len());
+        Ok(listings)
     }
 
-    async fn click_next_button_in_modal(&self) -> Result<()> {
-        let script = r#"
-                function clickNextButtonInModal() {
-                    const modal = document.querySelector('div.artdeco-modal');
-                    if (!modal) {
-                        return "Modal not found";
-                    }
-                    
-                    const footer = modal.querySelector('footer');
-                    if (!footer) {
-                        return "Modal footer not found";
-                    }
-    
-                    const nextButton = Array.from(footer.querySelectorAll('button.artdeco-button--primary'))
-                        .find(button => button.textContent.trim().toLowerCase() === 'next');
-                    
-                    if (!nextButton) {
-                        return "Next button not found in modal footer";
-                    }
-                    
-                    nextButton.click();
-                    return "Successfully clicked the Next button in modal footer";
+    /// Selects each listing in turn to read its details pane.
+    async fn inspect_all(&self, listings: &mut [JobListing]) -> Result<()> {
+        let select_script = format!(
+            "{}\n{}",
+            JOB_ID_OF,
+            r#"
+        async function selectJob(id, sel) {
+            const list = pick(sel.results_list);
+            const item = list && pickAll(sel.result_item, list)
+                .find(item => jobIdOf(item, sel.job_id_element) === id);
+            if (!item) return failed('not_found', `job ${id} in the results list`);
+            item.click();
+            const selected = await waitFor(
+                () => new URLSearchParams(window.location.search).get('currentJobId') === id,
+                3000
+            );
+            return selected ? ok('selected', id) : failed('unexpected_page', `job ${id} did not open`);
+        }
+        return selectJob(arguments[0], arguments[1]);
+        "#
+        );
+        let selectors = self
+            .selectors
+            .json(&["results_list", "result_item", "job_id_element"]);
+
+        for listing in listings.iter_mut() {
+            let step = self
+                .session
+                .run_step_with_args(
+                    "select_job",
+                    &select_script,
+                    vec![listing.id.clone().into(), selectors.clone()],
+                )
+                .await;
+            match step {
+                Ok(_) => listing.merge_details(self.read_job_details().await?),
+                Err(e @ (ScrapeError::SelectorMissing(_) | ScrapeError::UnexpectedPage(_))) => {
+                    listing.warnings.push(format!("details: {}", e))
                 }
-                return clickNextButtonInModal();
-            "#;
-
-        let result: Value = self.client.execute(script, vec![]).await?;
-
-        match result.as_str() {
-            Some("Successfully clicked the Next button in modal footer") => {
-                println!("Successfully clicked the Next button in modal footer");
-                Ok(())
+                Err(e) => return Err(e),
             }
-            Some("Next button not found in modal footer") => {
-                println!("Next button not found. The application process might be complete.");
-                Ok(())
-            }
-            Some(error_message) => Err(anyhow!("Error: {}", error_message)),
-            None => Err(anyhow!("Unexpected result from JavaScript execution")),
         }
+        Ok(())
     }
 
-    async fn handle_sponsorship_question(&self) -> Result<bool> {
+    /// Reads the details pane of the selected job.
+    async fn read_job_details(&self) -> Result<RawJobDetails> {
         let script = r#"
-                function handleSponsorshipQuestion() {
-                    const questionLegend = Array.from(document.querySelectorAll('legend'))
-                        .find(legend => legend.textContent.includes('Will you now or in the future require sponsorship for employment visa status?'));
-                    
-                    if (!questionLegend) {
-                        return "Question not found";
-                    }
-                    
-                    const formElement = questionLegend.closest('.radio-button-form-component-formElement-urn-li-jobs-applyformcommon-easyApplyFormElement-4048251551-5319829281-multipleChoice');
-                    if (!formElement) {
-                        return "Form element not found";
-                    }
-                    
-                    const yesRadio = formElement.querySelector('input[value="Yes"]');
-                    if (!yesRadio) {
-                        return "Yes option not found";
-                    }
-                    
-                    yesRadio.click();
-                    return "Successfully selected 'Yes' for sponsorship question";
-                }
-                return handleSponsorshipQuestion();
-            "#;
-
-        let result: Value = self.client.execute(script, vec![]).await?;
+            const sel = arguments[0];
+            const pane = pick(sel.job_details);
+            const text = chain => {
+                const el = pane && pick(chain, pane);
+                return el ? el.textContent : null;
+            };
+            const all = chain => pane
+                ? pickAll(chain, pane).map(el => el.textContent.trim()).filter(Boolean)
+                : [];
+            return {
+                title: text(sel.details_title),
+                company: text(sel.details_company),
+                insights: all(sel.details_insights),
+                summary: text(sel.details_summary),
+                apply_label: text(sel.apply_button),
+            };
+        "#;
+        let selectors = self.selectors.json(&[
+            "job_details",
+            "details_title",
+            "details_company",
+            "details_insights",
+            "details_summary",
+            "apply_button",
+        ]);
+        self.session
+            .execute_with_args(script, vec![selectors])
+            .await
+    }
 
-        match result.as_str() {
-            Some("Successfully selected 'Yes' for sponsorship question") => {
-                println!("Successfully handled sponsorship question");
-                Ok(true)
+    /// Emits listings not yet emitted this run.
+    fn emit_new_listings(&self, listings: &[JobListing]) -> Result<()> {
+        let mut emitted = self.emitted.lock().unwrap_or_else(|e| e.into_inner());
+        for listing in listings {
+            if emitted.insert(listing.id.clone()) {
+                self.output.emit(listing)?;
             }
-            Some("Question not found") => {
-                println!("Sponsorship question not found. Ending process.");
-                Ok(false)
-            }
-            Some(error_message) => Err(anyhow!("Error: {}", error_message)),
-            None => Err(anyhow!("Unexpected result from JavaScript execution")),
         }
+        Ok(())
     }
 
-    async fn handle_review_and_submit(&self) -> Result<()> {
+    async fn click_apply_button(&self) -> Result<()> {
         let script = r#"
-            async function handleReviewAndSubmit() {
-                function clickButtonIfExists(buttonText) {
-                    const modal = document.querySelector('div.artdeco-modal');
-                    if (!modal) return false;
-                    
-                    const footer = modal.querySelector('footer');
-                    if (!footer) return false;
-    
-                    const button = Array.from(footer.querySelectorAll('button.artdeco-button--primary'))
-                        .find(button => button.textContent.trim().toLowerCase() === buttonText.toLowerCase());
-                    
-                    if (button) {
-                        button.click();
-                        return true;
-                    }
-                    return false;
+            function clickApplyButton(sel) {
+                const container = pick(sel.job_details);
+                if (!container) {
+                    return failed('not_found', 'job details container');
                 }
-    
-                if (clickButtonIfExists('Review')) {
-                    await new Promise(resolve => setTimeout(resolve, 2000)); // Wait for 2 seconds
-                    if (clickButtonIfExists('Submit application')) {
-                        return "Successfully reviewed and submitted application";
-                    } else {
-                        return "Reviewed but couldn't find submit button";
-                    }
+                
+                const applyButton = pick(sel.apply_button, container);
+                if (!applyButton) {
+                    return failed('not_found', 'apply button');
                 }
                 
-                return "Review button not found";
+                applyButton.click();
+                return ok('clicked');
             }
-            return handleReviewAndSubmit();
-            "#;
+            return clickApplyButton(arguments[0]);
+        "#;
 
-        let result: Value = self.client.execute(script, vec![]).await?;
+        self.session
+            .run_step_with_args(
+                "click_apply_button",
+                script,
+                vec![self.selectors.json(&["job_details", "apply_button"])],
+            )
+            .await?;
+        println!("Successfully clicked the apply button");
+        Ok(())
+    }
 
-        match result.as_str() {
-            Some("Successfully reviewed and submitted application") => {
-                println!("Application reviewed and submitted");
-                Ok(())
+    /// Captures the review page and, as `submit_mode` allows, submits it.
+    /// Returns the terminal state if the walk stops here instead.
+    async fn submit_application(
+        &self,
+        capture: &mut ApplicationCapture,
+    ) -> Result<Option<ApplyResult>> {
+        self.capture_form_page(capture).await?;
+
+        match self.submit_mode {
+            SubmitMode::Submit => {}
+            SubmitMode::DryRun => {
+                println!("Dry run: stopping before submitting the application");
+                return Ok(Some(ApplyResult::DryRun));
             }
-            Some("Reviewed but couldn't find submit button") => Err(anyhow!(
-                "Application was reviewed but submit button was not found"
-            )),
-            Some("Review button not found") => {
-                println!("Review button not found, continuing with the process");
-                Ok(())
+            SubmitMode::Confirm => {
+                if !self.confirm_submit(capture).await? {
+                    println!("Submission declined");
+                    return Ok(Some(ApplyResult::Declined));
+                }
             }
-            Some(error_message) => Err(anyhow!("Error: {}", error_message)),
-            None => Err(anyhow!("Unexpected result from JavaScript execution")),
         }
-    }
-
-    async fn take_screenshot(&self, filename: &str) -> Result<()> {
-        let screenshot = self.client.screenshot().await?;
-        std::fs::write(filename, &screenshot)?;
-        println!("Screenshot saved as {}", filename);
-        Ok(())
-    }
-
-    async fn print_current_url(&self) -> Result<()> {
-        let current_url = self.client.current_url().await?;
-        println!("Current URL: {:?}", current_url);
-        Ok(())
-    }
 
-    async fn print_page_title(&self) -> Result<()> {
-        let title = self.client.title().await?;
-        println!("Page title: {:?}", title);
-        Ok(())
+        // Found by its role and name rather than a selector, so a restyled
+        // footer cannot turn this into a click on "Next" or "Review".
+        let method = self
+            .session
+            .click(&ClickTarget::role("button").with_text("Submit application"))
+            .await?;
+        debug_println!("Submit button clicked: {:?}", method);
+        println!("Submitted the application");
+        Ok(None)
     }
 
+    #[allow(dead_code)]
     fn find_verify_button(screenshot_path: &str) -> Result<(u32, u32)> {
-        let img = image::open(screenshot_path)?;
+        let img = image::open(screenshot_path).map_err(anyhow::Error::from)?;
         let button_color = Rgba([0, 0, 0, 255]); // Black text color
         let (width, height) = img.dimensions();
         for y in (height / 2)..height {
             for x in 0..width {
                 let pixel = img.get_pixel(x, y);
-                if pixel == button_color {
-                    if Self::is_verify_text(&img, x, y) {
-                        return Ok((x + 30, y + 15)); // Adjust these offsets as needed
-                    }
+                if pixel == button_color && Self::is_verify_text(&img, x, y) {
+                    return Ok((x + 30, y + 15)); // Adjust these offsets as needed
                 }
             }
         }
-        Err(anyhow!("Could not find 'Verify' button in the screenshot"))
+        Err(ScrapeError::UnexpectedPage(
+            "could not find 'Verify' button in the screenshot".to_string(),
+        ))
     }
 
+    #[allow(dead_code)]
     fn is_verify_text(img: &image::DynamicImage, x: u32, y: u32) -> bool {
         let expected_colors = [
             (0, 0),
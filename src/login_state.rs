@@ -0,0 +1,377 @@
+use crate::common::BrowserSession;
+use crate::error::{Result, ScrapeError};
+use regex::{Regex, RegexBuilder};
+use serde::Deserialize;
+use serde_json::json;
+use std::collections::HashSet;
+use std::fmt;
+
+/// Whether the browser session is logged in to a site.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoginState {
+    LoggedIn,
+    LoggedOut,
+    /// The signals disagree or are too weak to call, or the site is asking
+    /// for a verification step; see [`LoginCheck::challenge`].
+    Unknown,
+}
+
+impl fmt::Display for LoginState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            LoginState::LoggedIn => "logged in",
+            LoginState::LoggedOut => "logged out",
+            LoginState::Unknown => "unknown",
+        })
+    }
+}
+
+/// What a signal looks at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SignalKind {
+    /// An element matching the CSS selector `pattern` is on the page.
+    Selector,
+    /// The page URL matches the regex `pattern`.
+    Url,
+    /// The visible page text matches the regex `pattern`, ignoring case.
+    Text,
+    /// A cookie named `pattern` is set, including HttpOnly ones.
+    Cookie,
+}
+
+/// What a signal firing says about the session.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Indicates {
+    LoggedIn,
+    LoggedOut,
+    /// A checkpoint, captcha or two-step verification page.
+    Challenge,
+}
+
+/// One `login_signals` entry of a scraper's config.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LoginSignal {
+    /// Shown in logs when the signal fires.
+    pub name: String,
+    pub kind: SignalKind,
+    pub pattern: String,
+    pub indicates: Indicates,
+    #[serde(default = "default_weight")]
+    pub weight: u32,
+}
+
+fn default_weight() -> u32 {
+    1
+}
+
+impl LoginSignal {
+    pub fn new(
+        name: &str,
+        kind: SignalKind,
+        pattern: &str,
+        indicates: Indicates,
+        weight: u32,
+    ) -> Self {
+        Self {
+            name: name.to_string(),
+            kind,
+            pattern: pattern.to_string(),
+            indicates,
+            weight,
+        }
+    }
+}
+
+/// The outcome of one [`LoginDetector::detect`], with the evidence for it.
+#[derive(Debug)]
+pub struct LoginCheck {
+    pub state: LoginState,
+    /// Challenge signals reached the threshold; the state is
+    /// [`LoginState::Unknown`] whatever the other scores are.
+    pub challenge: bool,
+    pub logged_in_score: u32,
+    pub logged_out_score: u32,
+    pub challenge_score: u32,
+    /// Names of the signals that fired, with their weights.
+    pub fired: Vec<(String, u32)>,
+}
+
+impl fmt::Display for LoginCheck {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}{} (logged in {}, logged out {}, challenge {}",
+            self.state,
+            if self.challenge { ", challenge" } else { "" },
+            self.logged_in_score,
+            self.logged_out_score,
+            self.challenge_score
+        )?;
+        if self.fired.is_empty() {
+            f.write_str("; no signals fired)")
+        } else {
+            let fired: Vec<String> = self
+                .fired
+                .iter()
+                .map(|(name, weight)| format!("{} +{}", name, weight))
+                .collect();
+            write!(f, "; fired: {})", fired.join(", "))
+        }
+    }
+}
+
+enum Matcher {
+    Selector(String),
+    Url(Regex),
+    Text(Regex),
+    Cookie(String),
+}
+
+struct Signal {
+    name: String,
+    matcher: Matcher,
+    indicates: Indicates,
+    weight: u32,
+}
+
+/// Scores a page's login state from weighted signals. A state is only
+/// called when its score beats the opposing one by at least the threshold,
+/// so no single weak signal, such as a missing login form, decides it.
+pub struct LoginDetector {
+    signals: Vec<Signal>,
+    threshold: u32,
+}
+
+impl LoginDetector {
+    pub fn new(signals: Vec<LoginSignal>, threshold: u32) -> Result<Self> {
+        if threshold < 1 {
+            return Err(ScrapeError::Config(
+                "login_threshold must be at least 1".to_string(),
+            ));
+        }
+        let regex = |signal: &LoginSignal| {
+            RegexBuilder::new(&signal.pattern)
+                .case_insensitive(signal.kind == SignalKind::Text)
+                .build()
+                .map_err(|e| {
+                    ScrapeError::Config(format!("invalid login signal '{}': {}", signal.name, e))
+                })
+        };
+        let signals = signals
+            .iter()
+            .map(|signal| {
+                let matcher = match signal.kind {
+                    SignalKind::Selector => Matcher::Selector(signal.pattern.clone()),
+                    SignalKind::Url => Matcher::Url(regex(signal)?),
+                    SignalKind::Text => Matcher::Text(regex(signal)?),
+                    SignalKind::Cookie => Matcher::Cookie(signal.pattern.clone()),
+                };
+                Ok(Signal {
+                    name: signal.name.clone(),
+                    matcher,
+                    indicates: signal.indicates,
+                    weight: signal.weight,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self { signals, threshold })
+    }
+
+    /// Evaluates every signal against the current page.
+    pub async fn detect(&self, session: &BrowserSession) -> Result<LoginCheck> {
+        #[derive(Deserialize)]
+        struct Page {
+            url: String,
+            text: String,
+            /// Per selector: whether it matched, or why it is invalid.
+            selectors: Vec<std::result::Result<bool, String>>,
+        }
+
+        let selectors: Vec<&str> = self
+            .signals
+            .iter()
+            .filter_map(|signal| match &signal.matcher {
+                Matcher::Selector(selector) => Some(selector.as_str()),
+                _ => None,
+            })
+            .collect();
+        let page: Page = session
+            .execute_with_args(
+                r#"
+                const [selectors] = arguments;
+                return {
+                    url: window.location.href,
+                    text: document.body ? document.body.innerText : '',
+                    selectors: selectors.map(selector => {
+                        try {
+                            return { Ok: document.querySelector(selector) !== null };
+                        } catch (e) {
+                            return { Err: e.message };
+                        }
+                    }),
+                };
+                "#,
+                vec![json!(selectors)],
+            )
+            .await?;
+        let cookies: HashSet<String> = if self
+            .signals
+            .iter()
+            .any(|signal| matches!(signal.matcher, Matcher::Cookie(_)))
+        {
+            session
+                .client()
+                .get_all_cookies()
+                .await?
+                .iter()
+                .map(|cookie| cookie.name().to_string())
+                .collect()
+        } else {
+            HashSet::new()
+        };
+
+        let mut selector_results = page.selectors.into_iter();
+        let mut fired_signals = Vec::new();
+        for signal in &self.signals {
+            let fired = match &signal.matcher {
+                Matcher::Selector(_) => {
+                    selector_results.next().unwrap_or(Ok(false)).map_err(|e| {
+                        ScrapeError::Config(format!(
+                            "invalid login signal '{}': {}",
+                            signal.name, e
+                        ))
+                    })?
+                }
+                Matcher::Url(regex) => regex.is_match(&page.url),
+                Matcher::Text(regex) => regex.is_match(&page.text),
+                Matcher::Cookie(name) => cookies.contains(name),
+            };
+            if fired {
+                fired_signals.push((signal.name.clone(), signal.indicates, signal.weight));
+            }
+        }
+        Ok(score(fired_signals, self.threshold))
+    }
+}
+
+/// Sums the weights of the signals that fired and calls the state. Scores
+/// saturate, and a state is called on the margin between them, so huge
+/// configured weights cannot wrap around and flip the call.
+fn score(fired: Vec<(String, Indicates, u32)>, threshold: u32) -> LoginCheck {
+    let mut check = LoginCheck {
+        state: LoginState::Unknown,
+        challenge: false,
+        logged_in_score: 0,
+        logged_out_score: 0,
+        challenge_score: 0,
+        fired: Vec::new(),
+    };
+    for (name, indicates, weight) in fired {
+        match indicates {
+            Indicates::LoggedIn => {
+                check.logged_in_score = check.logged_in_score.saturating_add(weight)
+            }
+            Indicates::LoggedOut => {
+                check.logged_out_score = check.logged_out_score.saturating_add(weight)
+            }
+            Indicates::Challenge => {
+                check.challenge_score = check.challenge_score.saturating_add(weight)
+            }
+        }
+        check.fired.push((name, weight));
+    }
+
+    if check.challenge_score >= threshold {
+        check.challenge = true;
+    } else if check.logged_in_score.saturating_sub(check.logged_out_score) >= threshold {
+        check.state = LoginState::LoggedIn;
+    } else if check.logged_out_score.saturating_sub(check.logged_in_score) >= threshold {
+        check.state = LoginState::LoggedOut;
+    }
+    check
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Indicates::*;
+
+    fn fired(signals: &[(Indicates, u32)]) -> Vec<(String, Indicates, u32)> {
+        signals
+            .iter()
+            .enumerate()
+            .map(|(i, &(indicates, weight))| (format!("signal {}", i), indicates, weight))
+            .collect()
+    }
+
+    #[test]
+    fn logged_in_when_its_score_beats_logged_out_by_the_threshold() {
+        let check = score(fired(&[(LoggedIn, 3), (LoggedIn, 2), (LoggedOut, 2)]), 3);
+        assert_eq!(check.state, LoginState::LoggedIn);
+        assert!(!check.challenge);
+        assert_eq!((check.logged_in_score, check.logged_out_score), (5, 2));
+        assert_eq!(check.fired.len(), 3);
+    }
+
+    #[test]
+    fn logged_out_when_its_score_beats_logged_in_by_the_threshold() {
+        let check = score(fired(&[(LoggedOut, 3)]), 3);
+        assert_eq!(check.state, LoginState::LoggedOut);
+        assert!(!check.challenge);
+    }
+
+    #[test]
+    fn challenge_overrides_both_states() {
+        for other in [LoggedIn, LoggedOut] {
+            let check = score(fired(&[(other, 10), (Challenge, 3)]), 3);
+            assert_eq!(check.state, LoginState::Unknown);
+            assert!(check.challenge);
+            assert_eq!(check.challenge_score, 3);
+        }
+    }
+
+    #[test]
+    fn unknown_when_both_sides_score() {
+        let check = score(fired(&[(LoggedIn, 3), (LoggedOut, 2)]), 3);
+        assert_eq!(check.state, LoginState::Unknown);
+        assert!(!check.challenge);
+
+        let check = score(Vec::new(), 3);
+        assert_eq!(check.state, LoginState::Unknown);
+    }
+
+    #[test]
+    fn huge_weights_and_thresholds_saturate_instead_of_wrapping() {
+        let check = score(fired(&[(LoggedIn, u32::MAX), (LoggedIn, u32::MAX)]), 3);
+        assert_eq!(check.logged_in_score, u32::MAX);
+        assert_eq!(check.state, LoginState::LoggedIn);
+
+        // Wrapped, the logged in score would be 1 and the state logged out.
+        let check = score(
+            fired(&[(LoggedOut, 3), (LoggedIn, u32::MAX), (LoggedIn, 2)]),
+            3,
+        );
+        assert_eq!(check.state, LoginState::LoggedIn);
+
+        let check = score(fired(&[(LoggedIn, 5)]), u32::MAX);
+        assert_eq!(check.state, LoginState::Unknown);
+
+        let check = score(fired(&[(LoggedIn, u32::MAX), (LoggedOut, u32::MAX)]), 1);
+        assert_eq!(check.state, LoginState::Unknown);
+
+        let check = score(fired(&[(Challenge, u32::MAX), (Challenge, 1)]), u32::MAX);
+        assert!(check.challenge);
+    }
+
+    #[test]
+    fn rejects_a_zero_threshold() {
+        assert!(matches!(
+            LoginDetector::new(Vec::new(), 0),
+            Err(ScrapeError::Config(_))
+        ));
+        assert!(LoginDetector::new(Vec::new(), 1).is_ok());
+    }
+}
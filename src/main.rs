@@ -1,56 +1,258 @@
 use anyhow::{anyhow, Result};
-use fantoccini::Client;
 use std::io::{self, Write};
-use std::time::Duration;
+use std::process::ExitCode;
+use std::sync::Arc;
 
+mod artifacts;
+mod cli;
+mod click;
 mod common;
+mod config;
+mod credentials;
+mod error;
+mod job_answers;
+mod job_application;
+mod job_ledger;
+mod job_listing;
 mod job_scraper;
+mod login_state;
+mod movie_info;
 mod movie_scraper;
+mod movie_store;
+mod output;
+mod posters;
+mod retry;
+mod scraper;
+mod selectors;
+mod session_store;
+mod step;
+mod wait;
 
-use common::connect_with_retry;
-use job_scraper::JobScraper;
-use movie_scraper::MovieScraper;
+use artifacts::{prune_runs, ArtifactKind, RunArtifacts};
+use cli::Invocation;
+use common::SessionFactory;
+use config::Config;
+use error::ScrapeError;
+use output::Output;
+use scraper::{ScrapeContext, Scraper};
+use session_store::SessionStore;
 
-fn prompt_user() -> Result<String> {
+fn prompt_user(registry: &[Box<dyn Scraper>]) -> Result<&dyn Scraper> {
     println!("Which service would you like to use?");
-    println!("1. Job Scraper");
-    println!("2. Movie Scraper");
-    print!("Enter your choice (1 or 2): ");
+    for (index, scraper) in registry.iter().enumerate() {
+        println!("{}. {}", index + 1, scraper.description());
+    }
+    print!("Enter your choice (1-{}): ", registry.len());
     io::stdout().flush()?;
 
     let mut choice = String::new();
     io::stdin().read_line(&mut choice)?;
-    Ok(choice.trim().to_string())
+
+    choice
+        .trim()
+        .parse::<usize>()
+        .ok()
+        .and_then(|n| n.checked_sub(1))
+        .and_then(|index| registry.get(index))
+        .map(|scraper| scraper.as_ref())
+        .ok_or_else(|| anyhow!("Invalid choice. Please enter 1-{}.", registry.len()))
 }
 
-fn main() -> Result<()> {
+fn list_scrapers(registry: &[Box<dyn Scraper>]) {
+    for scraper in registry {
+        println!("{:<8}{}", scraper.name(), scraper.description());
+        for field in scraper.config_schema() {
+            println!(
+                "        [scrapers.{}] {} = {}  # {}",
+                scraper.name(),
+                field.key,
+                field.default,
+                field.description
+            );
+        }
+    }
+}
+
+/// Restores the saved browser session, if any. A session that cannot be
+/// restored only costs a fresh login, so failures are reported and ignored.
+async fn restore_session(session: &common::BrowserSession, store: &SessionStore) {
+    let saved = match store.load() {
+        Ok(Some(saved)) => saved,
+        Ok(None) => return,
+        Err(e) => {
+            println!("Not restoring the saved session: {:#}", e);
+            return;
+        }
+    };
+    match session.restore_state(&saved).await {
+        Ok(()) => println!(
+            "Restored {} cookies for {} saved {}",
+            saved.cookies.len(),
+            saved.origin,
+            saved.saved_at.format("%Y-%m-%d %H:%M")
+        ),
+        Err(e) => println!("Not restoring the saved session: {}", e),
+    }
+}
+
+/// Saves the browser session for the next run, reporting failures without
+/// failing the run.
+async fn save_session(session: &common::BrowserSession, store: &SessionStore) {
+    let result = match session.export_state().await {
+        Ok(Some(state)) => store.save(&state).map(|()| Some(state.cookies.len())),
+        Ok(None) => Ok(None),
+        Err(e) => Err(e.into()),
+    };
+    match result {
+        Ok(Some(count)) => {
+            debug_println!("Saved {} cookies to {}", count, store.path().display())
+        }
+        Ok(None) => {}
+        Err(e) => println!("Could not save the browser session: {:#}", e),
+    }
+}
+
+fn main() -> ExitCode {
+    match run() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error: {:#}", e);
+            let code = e
+                .downcast_ref::<ScrapeError>()
+                .map_or(1, ScrapeError::exit_code);
+            ExitCode::from(code)
+        }
+    }
+}
+
+fn run() -> Result<()> {
+    let registry = scraper::registry();
+    let (args, invocation) = cli::parse(&registry)?;
+    common::set_verbosity(args.verbose);
+
+    let scraper = match invocation {
+        Invocation::ListScrapers => {
+            list_scrapers(&registry);
+            return Ok(());
+        }
+        Invocation::ClearSession(name) => {
+            let config = Config::load(args.config.as_deref())?;
+            let removed = session_store::clear(&config.session, name.as_deref())?;
+            for path in &removed {
+                println!("Removed {}", path.display());
+            }
+            if removed.is_empty() {
+                println!("No saved sessions in {}", config.session.dir.display());
+            }
+            return Ok(());
+        }
+        Invocation::Run(name) => {
+            scraper::find(&registry, &name).ok_or_else(|| anyhow!("Unknown scraper '{}'", name))?
+        }
+        Invocation::Interactive => prompt_user(&registry)?,
+    };
+
+    let mut config = Config::load(args.config.as_deref())?;
+    args.browser.apply_to(&mut config.browser);
+
+    let runs_dir = args.runs_dir.clone().unwrap_or(config.runs.dir.clone());
+    let artifacts = Arc::new(RunArtifacts::create(&runs_dir, scraper.name())?);
+    let result = run_scraper(scraper, &args, config, &runs_dir, &artifacts);
+
+    match &result {
+        Ok(()) => artifacts.finish("succeeded")?,
+        Err(e) => {
+            artifacts.log(&format!("Run failed: {:#}", e))?;
+            artifacts.finish("failed")?;
+        }
+    }
+    result
+}
+
+/// Everything after the run directory exists, so that [`run`] records the
+/// outcome in its manifest however this ends.
+fn run_scraper(
+    scraper: &dyn Scraper,
+    args: &cli::GlobalArgs,
+    mut config: Config,
+    runs_dir: &std::path::Path,
+    artifacts: &Arc<RunArtifacts>,
+) -> Result<()> {
+    for pruned in prune_runs(runs_dir, &config.runs.retention)? {
+        debug_println!("Pruned old run {}", pruned.display());
+    }
+    println!("Writing run artifacts to {}", artifacts.root().display());
+
+    let format = args.format.unwrap_or(config.output.format);
+    let output_path = args.output.clone().unwrap_or_else(|| {
+        artifacts.path(
+            ArtifactKind::Data,
+            &format!("{}.{}", scraper.name(), format.extension()),
+        )
+    });
+    let output = Output::new(
+        output::open_sink(format, &output_path, scraper.name())?,
+        output_path,
+    );
+
     // Create a new runtime
     let runtime = tokio::runtime::Runtime::new()?;
 
     // Use the runtime to run our async main
     runtime.block_on(async {
-        let client = connect_with_retry("http://localhost:9515", 5, Duration::from_secs(2)).await?;
+        let mut connect_policy = config.retry.policy("connect");
+        args.apply_to_connect_policy(&mut connect_policy);
+        let sessions = SessionFactory::new(
+            args.webdriver_url.clone(),
+            config.browser.clone(),
+            connect_policy,
+            artifacts.clone(),
+            config.waits,
+        );
 
-        let choice = prompt_user()?;
-
-        match choice.as_str() {
-            "1" => {
-                println!("Running Job Scraper...");
-                let job_scraper = JobScraper::new(client.clone());
-                job_scraper.scrape().await?;
-            }
-            "2" => {
-                println!("Running Movie Scraper...");
-                let movie_scraper = MovieScraper::new(client.clone());
-                movie_scraper.scrape().await?;
-            }
-            _ => {
-                return Err(anyhow!("Invalid choice. Please enter 1 or 2."));
-            }
+        let session = sessions.open().await?;
+        let session_store = config
+            .session
+            .persist
+            .then(|| SessionStore::new(&config.session, scraper.name()));
+        if let Some(store) = &session_store {
+            restore_session(&session, store).await;
         }
 
-        client.close().await?;
+        let ctx = ScrapeContext {
+            session,
+            sessions,
+            settings: config.scrapers.remove(scraper.name()).unwrap_or_default(),
+            output,
+            retry: config.retry.clone(),
+        };
+
+        println!("Running {}...", scraper.name());
+        // Finish the output and close the browser even when the scraper
+        // fails, so partial results are kept and no browser is left open.
+        let report = scraper.run(&ctx).await;
+        let written = ctx.output.finish().and_then(|written| {
+            artifacts.record(ArtifactKind::Data, ctx.output.path(), "output")?;
+            println!(
+                "Wrote {} records to {}",
+                written,
+                ctx.output.path().display()
+            );
+            Ok(())
+        });
+        if let Some(store) = &session_store {
+            save_session(&ctx.session, store).await;
+        }
+        let reported = report.map_err(anyhow::Error::from).and_then(|report| {
+            println!("{}", report.summary);
+            ctx.session.artifacts().log(&report.summary)
+        });
+        let closed = ctx.session.close().await;
 
+        // The scraper's own error decides the exit code, so it comes first.
+        reported?;
+        written?;
+        closed?;
         Ok(())
     })
 }
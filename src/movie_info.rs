@@ -0,0 +1,451 @@
+use crate::posters::PosterImage;
+use chrono::{Datelike, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// The first year a film could have been released; earlier years are
+/// parse errors, not history.
+const EARLIEST_YEAR: u16 = 1888;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MediaKind {
+    Movie,
+    Tv,
+}
+
+/// A listing card as the page script reports it: untrimmed text, not yet
+/// interpreted.
+#[derive(Debug, Deserialize)]
+pub struct RawMovieCard {
+    pub title: Option<String>,
+    /// Text of each `.fdi-item`/`.fdi-duration` in the card's info line,
+    /// which mixes year, rating, duration and season/episode counts.
+    #[serde(default)]
+    pub info: Vec<String>,
+    /// The `Movie`/`TV` label.
+    pub type_label: Option<String>,
+    pub quality: Option<String>,
+    pub poster_url: Option<String>,
+    pub detail_url: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MovieInfo {
+    /// Numeric site ID from the end of the detail URL.
+    pub id: Option<String>,
+    pub slug: Option<String>,
+    pub title: String,
+    pub kind: Option<MediaKind>,
+    pub year: Option<u16>,
+    pub rating: Option<f32>,
+    pub duration_minutes: Option<u32>,
+    /// Latest season and episode for TV shows.
+    pub season: Option<u32>,
+    pub episode: Option<u32>,
+    pub quality: Option<String>,
+    pub poster_url: Option<String>,
+    pub detail_url: Option<String>,
+    /// Name of the listing section the card was found in.
+    #[serde(default)]
+    pub section: String,
+    /// 1-based page of that section.
+    #[serde(default)]
+    pub page: u32,
+    /// Filled in by the optional poster download stage.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub poster: Option<PosterImage>,
+    /// Filled in by the optional detail page pass.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub details: Option<MovieDetails>,
+    /// Card or detail page text that could not be parsed or failed validation.
+    #[serde(default)]
+    pub warnings: Vec<String>,
+}
+
+impl MovieInfo {
+    /// The same movie can appear in several sections and pages; its ID
+    /// identifies it, falling back to the detail URL and then the title.
+    pub fn dedupe_key(&self) -> String {
+        self.id
+            .clone()
+            .or_else(|| self.detail_url.clone())
+            .unwrap_or_else(|| self.title.clone())
+    }
+}
+
+impl From<RawMovieCard> for MovieInfo {
+    fn from(raw: RawMovieCard) -> Self {
+        let mut warnings = Vec::new();
+
+        let title = match non_empty(raw.title) {
+            Some(title) => title,
+            None => {
+                warnings.push("missing title".to_string());
+                "Unknown".to_string()
+            }
+        };
+
+        let detail_url = non_empty(raw.detail_url);
+        let (slug, id) = detail_url
+            .as_deref()
+            .map(parse_slug_and_id)
+            .unwrap_or_default();
+        if detail_url.is_none() {
+            warnings.push("missing detail URL".to_string());
+        }
+
+        let mut info = InfoLine::default();
+        for text in &raw.info {
+            if let Err(warning) = info.classify(text.trim()) {
+                warnings.push(warning);
+            }
+        }
+
+        let label_kind = non_empty(raw.type_label).map(|label| match parse_kind(&label) {
+            Some(kind) => Some(kind),
+            None => {
+                warnings.push(format!("unknown type '{}'", label));
+                None
+            }
+        });
+        let url_kind = detail_url.as_deref().and_then(|url| {
+            let path = url.split("://").nth(1).unwrap_or(url);
+            if path.contains("/movie/") {
+                Some(MediaKind::Movie)
+            } else if path.contains("/tv/") {
+                Some(MediaKind::Tv)
+            } else {
+                None
+            }
+        });
+        let kind = label_kind
+            .flatten()
+            .or(info.tv.then_some(MediaKind::Tv))
+            .or(url_kind);
+
+        MovieInfo {
+            id,
+            slug,
+            title,
+            kind,
+            year: info.year,
+            rating: info.rating,
+            duration_minutes: info.duration_minutes,
+            season: info.season,
+            episode: info.episode,
+            quality: non_empty(raw.quality),
+            poster_url: non_empty(raw.poster_url),
+            detail_url,
+            section: String::new(),
+            page: 0,
+            poster: None,
+            details: None,
+            warnings,
+        }
+    }
+}
+
+/// One `Label: value` row of a detail page, with the text of any links in it.
+#[derive(Debug, Deserialize)]
+pub struct RawDetailRow {
+    pub value: String,
+    #[serde(default)]
+    pub links: Vec<String>,
+}
+
+/// A detail page as the page script reports it; rows are keyed by their
+/// lowercased label without the colon.
+#[derive(Debug, Deserialize)]
+pub struct RawMovieDetails {
+    #[serde(default)]
+    pub rows: HashMap<String, RawDetailRow>,
+    pub description: Option<String>,
+}
+
+/// What a movie's detail page adds to its listing card.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MovieDetails {
+    pub genres: Vec<String>,
+    pub cast: Vec<String>,
+    pub country: Vec<String>,
+    pub release_date: Option<NaiveDate>,
+    pub description: Option<String>,
+    pub production: Vec<String>,
+}
+
+impl MovieDetails {
+    /// Parses a detail page, adding anything unusable to `warnings`.
+    pub fn parse(mut raw: RawMovieDetails, warnings: &mut Vec<String>) -> Self {
+        let mut list = |labels: &[&str]| {
+            labels
+                .iter()
+                .find_map(|label| raw.rows.remove(*label))
+                .map(split_list)
+                .unwrap_or_default()
+        };
+        let genres = list(&["genre", "genres"]);
+        let cast = list(&["casts", "cast"]);
+        let country = list(&["country"]);
+        let production = list(&["production"]);
+
+        let release_date = raw
+            .rows
+            .remove("released")
+            .map(|row| row.value.trim().to_string())
+            .filter(|text| !text.is_empty() && !text.eq_ignore_ascii_case("N/A"))
+            .and_then(|text| {
+                let date = parse_date(&text);
+                if date.is_none() {
+                    warnings.push(format!("unrecognized release date '{}'", text));
+                }
+                date
+            });
+
+        MovieDetails {
+            genres,
+            cast,
+            country,
+            release_date,
+            description: non_empty(raw.description),
+            production,
+        }
+    }
+}
+
+/// Prefers the row's link texts, which are already one item each.
+fn split_list(row: RawDetailRow) -> Vec<String> {
+    let items: Vec<String> = if row.links.is_empty() {
+        row.value.split(',').map(str::to_string).collect()
+    } else {
+        row.links
+    };
+    items
+        .into_iter()
+        .filter_map(|item| non_empty(Some(item)))
+        .filter(|item| !item.eq_ignore_ascii_case("N/A"))
+        .collect()
+}
+
+fn parse_date(text: &str) -> Option<NaiveDate> {
+    ["%Y-%m-%d", "%B %d, %Y", "%b %d, %Y", "%d %B %Y"]
+        .iter()
+        .find_map(|format| NaiveDate::parse_from_str(text, format).ok())
+}
+
+/// What the card's info line has yielded so far.
+#[derive(Default)]
+struct InfoLine {
+    year: Option<u16>,
+    rating: Option<f32>,
+    duration_minutes: Option<u32>,
+    season: Option<u32>,
+    episode: Option<u32>,
+    tv: bool,
+}
+
+impl InfoLine {
+    /// Files one info item under the field it looks like, or explains why
+    /// it could not.
+    fn classify(&mut self, text: &str) -> Result<(), String> {
+        if text.is_empty() || text.eq_ignore_ascii_case("N/A") {
+            return Ok(());
+        }
+
+        if let Some(number) = strip_prefix_ignore_case(text, "SS") {
+            self.tv = true;
+            self.season = Some(parse_count(number, text)?);
+            return Ok(());
+        }
+        if let Some(number) = strip_prefix_ignore_case(text, "EPS") {
+            self.tv = true;
+            self.episode = Some(parse_count(number, text)?);
+            return Ok(());
+        }
+        if let Some(minutes) = parse_duration(text) {
+            self.duration_minutes = Some(minutes);
+            return Ok(());
+        }
+
+        if text.len() == 4 && text.bytes().all(|b| b.is_ascii_digit()) {
+            let year: u16 = text
+                .parse()
+                .map_err(|_| format!("invalid year '{}'", text))?;
+            let latest = chrono::Local::now().year() as u16 + 2;
+            if !(EARLIEST_YEAR..=latest).contains(&year) {
+                return Err(format!("year {} out of range", year));
+            }
+            self.year = Some(year);
+            return Ok(());
+        }
+
+        if let Ok(rating) = text.parse::<f32>() {
+            if !(0.0..=10.0).contains(&rating) {
+                return Err(format!("rating {} out of range", rating));
+            }
+            self.rating = Some(rating);
+            return Ok(());
+        }
+
+        Err(format!("unrecognized info '{}'", text))
+    }
+}
+
+fn non_empty(value: Option<String>) -> Option<String> {
+    value
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+fn parse_kind(label: &str) -> Option<MediaKind> {
+    match label.to_ascii_lowercase().as_str() {
+        "movie" => Some(MediaKind::Movie),
+        "tv" | "tv show" | "tv-show" => Some(MediaKind::Tv),
+        _ => None,
+    }
+}
+
+fn strip_prefix_ignore_case<'a>(text: &'a str, prefix: &str) -> Option<&'a str> {
+    let head = text.get(..prefix.len())?;
+    let rest = &text[prefix.len()..];
+    (head.eq_ignore_ascii_case(prefix) && rest.starts_with(|c: char| c.is_whitespace()))
+        .then(|| rest.trim())
+}
+
+fn parse_count(number: &str, text: &str) -> Result<u32, String> {
+    number
+        .parse()
+        .map_err(|_| format!("invalid count in '{}'", text))
+}
+
+/// Accepts `118m`, `118 min` and `1h 58m`. Totals too large for a `u32`
+/// are not durations.
+fn parse_duration(text: &str) -> Option<u32> {
+    let compact: String = text.split_whitespace().collect();
+    if compact.is_empty() {
+        return None;
+    }
+    let mut minutes: u32 = 0;
+    let mut rest = compact.as_str();
+    while !rest.is_empty() {
+        let digits = rest.find(|c: char| !c.is_ascii_digit())?;
+        let number: u32 = rest[..digits].parse().ok()?;
+        rest = &rest[digits..];
+        let unit_len = rest
+            .find(|c: char| c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let added = match &rest[..unit_len] {
+            "h" => number.checked_mul(60)?,
+            "m" | "min" | "mins" => number,
+            _ => return None,
+        };
+        minutes = minutes.checked_add(added)?;
+        rest = &rest[unit_len..];
+    }
+    Some(minutes)
+}
+
+/// Splits `/movie/watch-the-batman-19754` into `("the-batman", "19754")`.
+fn parse_slug_and_id(url: &str) -> (Option<String>, Option<String>) {
+    let path = url.split(['?', '#']).next().unwrap_or(url);
+    let Some(last) = path.trim_end_matches('/').rsplit('/').next() else {
+        return (None, None);
+    };
+    let last = last.strip_prefix("watch-").unwrap_or(last);
+    match last.rsplit_once('-') {
+        Some((slug, id)) if !id.is_empty() && id.bytes().all(|b| b.is_ascii_digit()) => {
+            (Some(slug.to_string()), Some(id.to_string()))
+        }
+        _ if !last.is_empty() => (Some(last.to_string()), None),
+        _ => (None, None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn classify(items: &[&str]) -> Result<InfoLine, String> {
+        let mut info = InfoLine::default();
+        for item in items {
+            info.classify(item)?;
+        }
+        Ok(info)
+    }
+
+    #[test]
+    fn classify_tells_year_rating_duration_and_episodes_apart() {
+        let info = classify(&["2022", "7.8", "176m"]).unwrap();
+        assert_eq!(info.year, Some(2022));
+        assert_eq!(info.rating, Some(7.8));
+        assert_eq!(info.duration_minutes, Some(176));
+        assert!(!info.tv);
+
+        let info = classify(&["SS 2", "EPS 10", "8"]).unwrap();
+        assert_eq!(info.season, Some(2));
+        assert_eq!(info.episode, Some(10));
+        assert_eq!(info.rating, Some(8.0));
+        assert_eq!(info.year, None);
+        assert!(info.tv);
+
+        let info = classify(&["1h 58m"]).unwrap();
+        assert_eq!(info.duration_minutes, Some(118));
+    }
+
+    #[test]
+    fn classify_skips_missing_values() {
+        let info = classify(&["N/A", "n/a", ""]).unwrap();
+        assert_eq!(info.year, None);
+        assert_eq!(info.rating, None);
+        assert_eq!(info.duration_minutes, None);
+    }
+
+    #[test]
+    fn classify_rejects_out_of_range_and_unknown_items() {
+        assert_eq!(classify(&["1700"]).err().unwrap(), "year 1700 out of range");
+        assert_eq!(classify(&["9999"]).err().unwrap(), "year 9999 out of range");
+        assert_eq!(
+            classify(&["11.5"]).err().unwrap(),
+            "rating 11.5 out of range"
+        );
+        assert_eq!(classify(&["-1"]).err().unwrap(), "rating -1 out of range");
+        assert_eq!(
+            classify(&["SS two"]).err().unwrap(),
+            "invalid count in 'SS two'"
+        );
+        assert_eq!(classify(&["HD"]).err().unwrap(), "unrecognized info 'HD'");
+    }
+
+    #[test]
+    fn parse_duration_rejects_overflow() {
+        assert_eq!(parse_duration("118 min"), Some(118));
+        assert_eq!(parse_duration("4294967295h"), None);
+        assert_eq!(parse_duration("4294967295m 1m"), None);
+    }
+
+    #[test]
+    fn parse_slug_and_id_handles_url_shapes() {
+        let slug_and_id = |slug: &str, id: &str| (Some(slug.to_string()), Some(id.to_string()));
+        assert_eq!(
+            parse_slug_and_id("/movie/watch-the-batman-19754"),
+            slug_and_id("the-batman", "19754")
+        );
+        assert_eq!(
+            parse_slug_and_id("/movie/the-batman-19754"),
+            slug_and_id("the-batman", "19754")
+        );
+        assert_eq!(
+            parse_slug_and_id("https://example.com/tv/watch-severance-78523/"),
+            slug_and_id("severance", "78523")
+        );
+        assert_eq!(
+            parse_slug_and_id("/movie/watch-dune-part-two-10412?ref=home#play"),
+            slug_and_id("dune-part-two", "10412")
+        );
+        assert_eq!(
+            parse_slug_and_id("/movie/watch-untitled"),
+            (Some("untitled".to_string()), None)
+        );
+        assert_eq!(parse_slug_and_id(""), (None, None));
+    }
+}
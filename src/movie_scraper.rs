@@ -1,255 +1,660 @@
-use crate::common::execute_script;
-use anyhow::{anyhow, Result};
-use fantoccini::Client;
-use serde::{Deserialize, Serialize};
+use crate::artifacts::ArtifactKind;
+use crate::common::{BrowserSession, SessionFactory};
+use crate::debug_println;
+use crate::error::{Result, ScrapeError};
+use crate::movie_info::{MovieDetails, MovieInfo, RawMovieCard, RawMovieDetails};
+use crate::movie_store::{MovieDiff, MovieStore};
+use crate::output::Output;
+use crate::posters::PosterDownloader;
+use crate::retry::RetryConfig;
+use crate::scraper::{ConfigField, ScrapeContext, ScrapeReport, Scraper};
+use crate::selectors::SelectorCatalog;
+use async_trait::async_trait;
+use futures::future::join_all;
+use futures::stream::{self, StreamExt};
+use serde::Deserialize;
+use serde_json::json;
+use std::collections::{HashSet, VecDeque};
+use std::path::PathBuf;
+use std::sync::Mutex;
 use std::time::Duration;
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct MovieInfo {
-    title: String,
-    year: Option<String>,
-    quality: Option<String>,
-    duration: Option<String>,
-    poster_url: Option<String>,
+const DEFAULT_HOME_URL: &str = "https://dopebox.to/home";
+const DEFAULT_MAX_PAGES: u32 = 5;
+const DEFAULT_ENRICH_CONCURRENCY: usize = 2;
+const DEFAULT_STORE: &str = "movie-store.json";
+const DEFAULT_POSTER_CONCURRENCY: usize = 4;
+const DEFAULT_THUMBNAIL_SIZE: u32 = 200;
+/// The built-in selector catalog; the `selectors` setting overrides entries.
+const DEFAULT_SELECTORS: &str = include_str!("../selectors/movies.toml");
+
+/// A listing to crawl, e.g. `{ name = "movies", url = "https://dopebox.to/movie" }`.
+#[derive(Debug, Clone, Deserialize)]
+struct Section {
+    name: String,
+    url: String,
 }
 
-pub struct MovieScraper {
-    client: Client,
-}
+pub struct MovieScraper;
+
+#[async_trait]
+impl Scraper for MovieScraper {
+    fn name(&self) -> &'static str {
+        "movies"
+    }
+
+    fn description(&self) -> &'static str {
+        "Scrape movie listings from Dopebox"
+    }
+
+    fn config_schema(&self) -> Vec<ConfigField> {
+        vec![
+            ConfigField {
+                key: "home_url",
+                description: "Listing page crawled when no sections are configured",
+                default: DEFAULT_HOME_URL,
+            },
+            ConfigField {
+                key: "sections",
+                description: "Listings to crawl, as [{ name, url }]",
+                default: "[{ name = \"home\", url = home_url }]",
+            },
+            ConfigField {
+                key: "max_pages",
+                description: "Pages to follow per section",
+                default: "5",
+            },
+            ConfigField {
+                key: "max_items",
+                description: "Stop once this many distinct movies are collected",
+                default: "unlimited",
+            },
+            ConfigField {
+                key: "enrich",
+                description: "Visit each movie's detail page for genres, cast and more",
+                default: "false",
+            },
+            ConfigField {
+                key: "enrich_concurrency",
+                description: "Browser sessions used for detail pages",
+                default: "2",
+            },
+            ConfigField {
+                key: "enrich_limit",
+                description: "Detail pages to visit at most",
+                default: "unlimited",
+            },
+            ConfigField {
+                key: "posters",
+                description: "Download, verify and thumbnail each poster into the run directory",
+                default: "false",
+            },
+            ConfigField {
+                key: "poster_concurrency",
+                description: "Posters downloaded at once",
+                default: "4",
+            },
+            ConfigField {
+                key: "thumbnail_size",
+                description: "Longest side of poster thumbnails, in pixels",
+                default: "200",
+            },
+            ConfigField {
+                key: "store",
+                description: "Movies seen across runs, compared against each run",
+                default: DEFAULT_STORE,
+            },
+            ConfigField {
+                key: "selectors",
+                description: "TOML file of selectors replacing built-in ones by element name; see selectors/movies.toml",
+                default: "built in",
+            },
+        ]
+    }
 
-impl MovieScraper {
-    pub fn new(client: Client) -> Self {
-        Self { client }
+    async fn run(&self, ctx: &ScrapeContext) -> Result<ScrapeReport> {
+        let home_url: String = ctx.setting("home_url", DEFAULT_HOME_URL.to_string())?;
+        let default_sections = vec![Section {
+            name: "home".to_string(),
+            url: home_url,
+        }];
+        let run = MovieRun {
+            session: &ctx.session,
+            sections: ctx.setting("sections", default_sections)?,
+            max_pages: ctx.setting("max_pages", DEFAULT_MAX_PAGES)?,
+            max_items: ctx.setting("max_items", None)?,
+            enrichment: Enrichment {
+                enabled: ctx.setting("enrich", false)?,
+                concurrency: ctx.setting("enrich_concurrency", DEFAULT_ENRICH_CONCURRENCY)?,
+                limit: ctx.setting("enrich_limit", None)?,
+            },
+            posters: Posters {
+                enabled: ctx.setting("posters", false)?,
+                concurrency: ctx.setting("poster_concurrency", DEFAULT_POSTER_CONCURRENCY)?,
+                thumbnail_size: ctx.setting("thumbnail_size", DEFAULT_THUMBNAIL_SIZE)?,
+            },
+            sessions: &ctx.sessions,
+            store: ctx.setting("store", PathBuf::from(DEFAULT_STORE))?,
+            output: &ctx.output,
+            retry: &ctx.retry,
+            selectors: SelectorCatalog::load(
+                "movies",
+                DEFAULT_SELECTORS,
+                ctx.setting::<Option<PathBuf>>("selectors", None)?
+                    .as_deref(),
+            )
+            .map_err(|e| ScrapeError::Config(format!("{:#}", e)))?,
+        };
+        let (movies, diff) = run.scrape().await?;
+        Ok(ScrapeReport {
+            summary: format!("Scraped {} movies ({})", movies.len(), diff.summary()),
+        })
     }
+}
+
+struct MovieRun<'a> {
+    session: &'a BrowserSession,
+    sections: Vec<Section>,
+    max_pages: u32,
+    max_items: Option<usize>,
+    enrichment: Enrichment,
+    posters: Posters,
+    sessions: &'a SessionFactory,
+    store: PathBuf,
+    output: &'a Output,
+    retry: &'a RetryConfig,
+    selectors: SelectorCatalog,
+}
+
+/// Settings for the detail page pass.
+struct Enrichment {
+    enabled: bool,
+    /// Sessions opened alongside the main one, each working through the
+    /// shared queue of detail URLs.
+    concurrency: usize,
+    limit: Option<usize>,
+}
+
+/// Settings for the poster download stage.
+struct Posters {
+    enabled: bool,
+    concurrency: usize,
+    thumbnail_size: u32,
+}
 
-    pub async fn scrape(&self) -> Result<()> {
-        self.navigate_to_dopebox().await?;
-        let movies = self.scrape_all_movies().await?;
+impl MovieRun<'_> {
+    async fn scrape(&self) -> Result<(Vec<MovieInfo>, MovieDiff)> {
+        let (mut movies, complete) = self.scrape_all_sections().await?;
         println!("Scraped {} movies", movies.len());
-        // Here you can decide what to do with the scraped movies
-        // For example, you could save them to a file or database
-        self.save_movies_to_file(&movies, "scraped_movies.json")
-            .await?;
+        if self.enrichment.enabled {
+            self.enrich(&mut movies).await?;
+        }
+        if self.posters.enabled {
+            self.download_posters(&mut movies).await?;
+        }
+        if !self.streams_listings() {
+            for movie in &movies {
+                self.output.emit(movie)?;
+            }
+        }
+        let diff = self.update_store(&movies, &complete)?;
         self.click_first_movie().await?;
         self.click_play_button().await?;
-        self.take_screenshot("final_play_page_screenshot.png")
-            .await?;
+        self.session.screenshot("final_play_page").await?;
+        Ok((movies, diff))
+    }
+
+    /// Merges this run into the persistent store and writes what changed
+    /// since the previous run to the run's data directory. Only sections in
+    /// `complete` were crawled to their last page, so only their movies can
+    /// count as removed.
+    fn update_store(&self, movies: &[MovieInfo], complete: &[String]) -> Result<MovieDiff> {
+        let mut store = MovieStore::load(&self.store)?;
+        let sections: Vec<&str> = complete.iter().map(String::as_str).collect();
+        let diff = store.update(movies, &sections, chrono::Local::now())?;
+        store.save(&self.store)?;
+
+        let artifacts = self.session.artifacts();
+        let path = artifacts.path(ArtifactKind::Data, "movies-diff.json");
+        std::fs::write(&path, serde_json::to_string_pretty(&diff)?)?;
+        artifacts.record(ArtifactKind::Data, &path, "movie_diff")?;
+        println!("Compared with {}: {}", self.store.display(), diff.summary());
+        Ok(diff)
+    }
+
+    /// Whether movies are emitted as the listing pages are read. Detail
+    /// pages and posters add to movies after that, so with either enabled
+    /// the records wait until the end of the run.
+    fn streams_listings(&self) -> bool {
+        !self.enrichment.enabled && !self.posters.enabled
+    }
+
+    /// Walks every section's pages until `max_pages` per section or
+    /// `max_items` overall, keeping the first sighting of each movie and
+    /// emitting it right away if [`Self::streams_listings`]. Also returns
+    /// the sections crawled to their last page, which a limit did not cut
+    /// short.
+    async fn scrape_all_sections(&self) -> Result<(Vec<MovieInfo>, Vec<String>)> {
+        let mut movies = Vec::new();
+        let mut seen = HashSet::new();
+        let mut complete = Vec::new();
+
+        'sections: for section in &self.sections {
+            let mut visited = HashSet::new();
+            let mut next_url = Some(section.url.clone());
+            let mut page = 1;
+
+            while let Some(url) = next_url.take() {
+                if page > self.max_pages {
+                    println!(
+                        "Section '{}' has more than {} pages; stopping there",
+                        section.name, self.max_pages
+                    );
+                    continue 'sections;
+                }
+                if !visited.insert(url.clone()) {
+                    break;
+                }
+                self.open_listing_page(section, page, &url).await?;
+
+                let cards = self.scrape_listing_page().await?;
+                let found = cards.len();
+                let mut added = 0;
+                for mut movie in cards {
+                    if !seen.insert(movie.dedupe_key()) {
+                        continue;
+                    }
+                    movie.section = section.name.clone();
+                    movie.page = page;
+                    if self.streams_listings() {
+                        self.output.emit(&movie)?;
+                    }
+                    movies.push(movie);
+                    added += 1;
+                    if self.max_items.is_some_and(|max| movies.len() >= max) {
+                        println!("Reached the limit of {} movies", movies.len());
+                        break 'sections;
+                    }
+                }
+                println!(
+                    "Section '{}' page {}: {} movies, {} new",
+                    section.name, page, found, added
+                );
+
+                next_url = self.next_page_url().await?;
+                page += 1;
+            }
+            complete.push(section.name.clone());
+        }
+
+        self.session.screenshot("after_scraping_all_movies").await?;
+        Ok((movies, complete))
+    }
+
+    /// Fills in [`MovieInfo::details`] from each movie's detail page, using
+    /// separate sessions so the main one stays on the listing. A page that
+    /// still fails after retries becomes a warning on its movie.
+    async fn enrich(&self, movies: &mut [MovieInfo]) -> Result<()> {
+        let queue: VecDeque<(usize, String)> = movies
+            .iter()
+            .enumerate()
+            .filter_map(|(index, movie)| Some((index, movie.detail_url.clone()?)))
+            .take(self.enrichment.limit.unwrap_or(usize::MAX))
+            .collect();
+        if queue.is_empty() {
+            return Ok(());
+        }
+
+        let mut workers = self.enrichment.concurrency.clamp(1, queue.len());
+        if workers > 1 && !self.sessions.supports_parallel() {
+            println!("A user data directory is set; enriching with a single session");
+            workers = 1;
+        }
+        println!("Enriching {} movies with {} sessions", queue.len(), workers);
+
+        let mut sessions = Vec::with_capacity(workers);
+        for _ in 0..workers {
+            match self.sessions.open().await {
+                Ok(session) => sessions.push(session),
+                // Carry on with the sessions already open rather than
+                // dropping them with the browsers still running.
+                Err(e) if !sessions.is_empty() => {
+                    println!(
+                        "Could not open another session: {}. Enriching with {}",
+                        e,
+                        sessions.len()
+                    );
+                    break;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        let queue = Mutex::new(queue);
+        let results = join_all(
+            sessions
+                .iter()
+                .map(|session| self.enrich_worker(session, &queue)),
+        )
+        .await;
+        for session in sessions {
+            if let Err(e) = session.close().await {
+                println!("Could not close an enrichment session: {}", e);
+            }
+        }
+
+        let mut enriched = 0;
+        for (index, result) in results.into_iter().flatten() {
+            let movie = &mut movies[index];
+            match result {
+                Ok(raw) => {
+                    movie.details = Some(MovieDetails::parse(raw, &mut movie.warnings));
+                    enriched += 1;
+                }
+                Err(e) => movie.warnings.push(format!("details: {}", e)),
+            }
+        }
+        println!("Enriched {} movies", enriched);
         Ok(())
     }
 
-    async fn navigate_to_dopebox(&self) -> Result<()> {
-        self.client.goto("https://dopebox.to/home").await?;
-        println!("Navigated to Dopebox");
-        self.take_screenshot("dopebox_home.png").await?;
+    /// Downloads each movie's poster; a poster that fails to download or
+    /// decode becomes a warning on its movie.
+    async fn download_posters(&self, movies: &mut [MovieInfo]) -> Result<()> {
+        let downloader =
+            PosterDownloader::new(self.session.artifacts(), self.posters.thumbnail_size)?;
+        let downloader = &downloader;
+        let urls: Vec<(usize, String)> = movies
+            .iter()
+            .enumerate()
+            .filter_map(|(index, movie)| Some((index, movie.poster_url.clone()?)))
+            .collect();
+        let results: Vec<_> = stream::iter(urls)
+            .map(|(index, url)| async move { (index, downloader.fetch(&url).await) })
+            .buffer_unordered(self.posters.concurrency.max(1))
+            .collect()
+            .await;
+
+        let mut downloaded = 0;
+        for (index, result) in results {
+            let movie = &mut movies[index];
+            match result {
+                Ok(poster) => {
+                    movie.poster = Some(poster);
+                    downloaded += 1;
+                }
+                Err(e) => movie.warnings.push(format!("poster: {:#}", e)),
+            }
+        }
+        println!("Downloaded {} posters", downloaded);
         Ok(())
     }
 
-    async fn scrape_all_movies(&self) -> Result<Vec<MovieInfo>> {
-        let script = r#"
-        function scrapeMovies() {
-            const movieElements = document.querySelectorAll('.film_list-wrap .flw-item');
-            return Array.from(movieElements).map(movie => {
-                const titleElement = movie.querySelector('.film-poster-ahref');
-                const yearElement = movie.querySelector('.fdi-item');
-                const qualityElement = movie.querySelector('.pick.film-poster-quality');
-                const durationElement = movie.querySelector('.fdi-duration');
-                const posterElement = movie.querySelector('.film-poster-img');
-                
-                return {
-                    title: titleElement ? titleElement.getAttribute('title') : 'Unknown',
-                    year: yearElement ? yearElement.textContent.trim() : null,
-                    quality: qualityElement ? qualityElement.textContent.trim() : null,
-                    duration: durationElement ? durationElement.textContent.trim() : null,
-                    poster_url: posterElement ? posterElement.getAttribute('data-src') : null
-                };
-            });
+    async fn enrich_worker(
+        &self,
+        session: &BrowserSession,
+        queue: &Mutex<VecDeque<(usize, String)>>,
+    ) -> Vec<(usize, Result<RawMovieDetails>)> {
+        let policy = self.retry.policy("movies.enrich");
+        let mut results = Vec::new();
+        loop {
+            let next = queue.lock().unwrap_or_else(|e| e.into_inner()).pop_front();
+            let Some((index, url)) = next else {
+                return results;
+            };
+            let result = policy
+                .run("enrich", |_| self.scrape_details(session, &url))
+                .await;
+            results.push((index, result));
         }
-        return JSON.stringify(scrapeMovies());
+    }
+
+    async fn scrape_details(&self, session: &BrowserSession, url: &str) -> Result<RawMovieDetails> {
+        let script = r#"
+        const sel = arguments[0];
+        const rows = {};
+        pickAll(sel.detail_row).forEach(row => {
+            const label = pick(sel.detail_label, row);
+            if (!label) return;
+            const key = label.textContent.replace(':', '').trim().toLowerCase();
+            const value = row.textContent.replace(label.textContent, '').trim();
+            const links = Array.from(row.querySelectorAll('a'), a => a.textContent.trim());
+            rows[key] = { value, links };
+        });
+        const description = pick(sel.description);
+        return { rows, description: description ? description.textContent : null };
         "#;
 
-        let result = execute_script(&self.client, script).await?;
-        let movies: Vec<MovieInfo> = serde_json::from_str(&result.as_str().unwrap_or("[]"))?;
+        session.client().goto(url).await?;
+        session
+            .wait()
+            .for_visible(self.selectors.get("detail_row"))
+            .await?;
+        debug_println!("Reading details from {}", url);
+        let selectors = self
+            .selectors
+            .json(&["detail_row", "detail_label", "description"]);
+        session.execute_with_args(script, vec![selectors]).await
+    }
 
-        self.take_screenshot("after_scraping_all_movies.png")
+    async fn open_listing_page(&self, section: &Section, page: u32, url: &str) -> Result<()> {
+        self.session.client().goto(url).await?;
+        println!("Navigated to section '{}' page {}", section.name, page);
+        self.session
+            .screenshot(&format!("{}_page_{}", section.name, page))
             .await?;
+        Ok(())
+    }
 
-        Ok(movies)
+    /// The absolute URL of the listing's next page, if it has one.
+    async fn next_page_url(&self) -> Result<Option<String>> {
+        let script = r#"
+        const next = pick(arguments[0]);
+        return next && next.href ? next.href : null;
+        "#;
+        self.session
+            .execute_with_args(script, vec![json!(self.selectors.get("next_page"))])
+            .await
     }
 
-    async fn save_movies_to_file(&self, movies: &[MovieInfo], filename: &str) -> Result<()> {
-        let json = serde_json::to_string_pretty(movies)?;
-        std::fs::write(filename, json)?;
-        println!("Saved {} movies to {}", movies.len(), filename);
-        Ok(())
+    async fn scrape_listing_page(&self) -> Result<Vec<MovieInfo>> {
+        let script = r#"
+        function scrapeMovies(sel) {
+            const text = el => el ? el.textContent : null;
+            return pickAll(sel.movie_card).map(movie => {
+                const titleElement = pick(sel.card_title, movie);
+                const qualityElement = pick(sel.card_quality, movie);
+                const typeElement = pick(sel.card_type, movie);
+                const posterElement = pick(sel.poster, movie);
+                const linkElement = pick(sel.card_link, movie);
+                const infoElements = pickAll(sel.card_info, movie);
+
+                return {
+                    title: titleElement ? titleElement.getAttribute('title') || text(titleElement) : null,
+                    info: Array.from(infoElements, text),
+                    type_label: text(typeElement),
+                    quality: text(qualityElement),
+                    poster_url: posterElement ? posterElement.getAttribute('data-src') || posterElement.src : null,
+                    detail_url: linkElement ? linkElement.href : null
+                };
+            });
+        }
+        return scrapeMovies(arguments[0]);
+        "#;
+
+        // Cards can render in batches; read them once the count has settled.
+        self.session
+            .wait()
+            .interval(Duration::from_millis(500))
+            .for_count_stable(self.selectors.get("movie_card"), Duration::from_secs(1))
+            .await?;
+        let selectors = self.selectors.json(&[
+            "movie_card",
+            "card_title",
+            "card_quality",
+            "card_type",
+            "poster",
+            "card_link",
+            "card_info",
+        ]);
+        let cards: Vec<RawMovieCard> = self
+            .session
+            .execute_with_args(script, vec![selectors])
+            .await?;
+        Ok(cards.into_iter().map(MovieInfo::from).collect())
     }
 
     async fn click_first_movie(&self) -> Result<()> {
         let script = r#"
-        function clickFirstMovie() {
-            const firstMovie = document.querySelector('.film_list-wrap .flw-item');
+        function clickFirstMovie(sel) {
+            const firstMovie = pick(sel.movie_card);
             if (!firstMovie) {
-                return "No movie found";
+                return failed('not_found', 'movie item');
             }
             
-            const link = firstMovie.querySelector('a');
+            const link = pick(sel.card_link, firstMovie);
             if (!link) {
-                return "No link found in the first movie item";
+                return failed('not_found', 'link in the first movie item');
             }
             
             const href = link.getAttribute('href');
             if (!href) {
-                return "No href attribute found in the link";
+                return failed('not_found', 'href attribute on the movie link');
             }
             
             window.movieHref = href;
             link.click();
             
-            return "Clicked on the first movie";
+            return ok('clicked', href);
         }
-        return clickFirstMovie();
+        return clickFirstMovie(arguments[0]);
         "#;
 
-        let mut attempts = 0;
-        const MAX_ATTEMPTS: u32 = 5;
-
-        while attempts < MAX_ATTEMPTS {
-            attempts += 1;
-            println!("Attempt {} to click on the first movie", attempts);
-
-            let result = execute_script(&self.client, script).await?;
-            self.take_screenshot(&format!("attempt_{}_before_click.png", attempts))
-                .await?;
+        self.retry
+            .policy("movies.click_first_movie")
+            .run_with_hook(
+                "click_first_movie",
+                |attempt| self.try_click_first_movie(script, attempt),
+                |attempt, _| async move {
+                    self.session
+                        .screenshot(&format!("attempt_{}_error", attempt))
+                        .await
+                        .map(drop)
+                },
+            )
+            .await
+    }
 
-            match result.as_str() {
-                Some("Clicked on the first movie") => {
-                    println!("Successfully clicked on the first movie");
+    async fn try_click_first_movie(&self, script: &str, attempt: u32) -> Result<()> {
+        println!("Attempt {} to click on the first movie", attempt);
+
+        let before = self.session.client().current_url().await?;
+        let result = self
+            .session
+            .run_step_with_args(
+                "click_first_movie",
+                script,
+                vec![self.selectors.json(&["movie_card", "card_link"])],
+            )
+            .await;
+        self.session
+            .screenshot(&format!("attempt_{}_before_click", attempt))
+            .await?;
+        result?;
+        println!("Successfully clicked on the first movie");
 
-                    tokio::time::sleep(Duration::from_secs(2)).await;
-                    self.take_screenshot(&format!("attempt_{}_after_click.png", attempts))
-                        .await?;
+        match self.session.wait().for_url_change(before.as_str()).await {
+            Ok(_) | Err(ScrapeError::NavigationTimeout(_)) => {}
+            Err(e) => return Err(e),
+        }
+        self.session
+            .screenshot(&format!("attempt_{}_after_click", attempt))
+            .await?;
 
-                    self.close_other_tabs().await?;
+        self.session.close_other_tabs().await?;
 
-                    if self.is_on_movie_page().await? {
-                        self.take_screenshot(&format!("attempt_{}_success.png", attempts))
-                            .await?;
-                        return Ok(());
-                    }
-                }
-                Some(error_msg) => {
-                    println!("Error: {}", error_msg);
-                    self.take_screenshot(&format!("attempt_{}_error.png", attempts))
-                        .await?;
-                }
-                _ => {
-                    println!("Unexpected result when clicking on the movie");
-                    self.take_screenshot(&format!("attempt_{}_unexpected.png", attempts))
-                        .await?;
-                }
-            }
-
-            println!("Attempt {} failed, retrying...", attempts);
-            tokio::time::sleep(Duration::from_secs(1)).await;
+        if !self.is_on_movie_page().await? {
+            return Err(ScrapeError::UnexpectedPage(
+                "click_first_movie: did not reach the movie page".to_string(),
+            ));
         }
-
-        Err(anyhow!(
-            "Failed to navigate to the movie page after {} attempts",
-            MAX_ATTEMPTS
-        ))
+        self.session
+            .screenshot(&format!("attempt_{}_success", attempt))
+            .await?;
+        Ok(())
     }
+
     async fn click_play_button(&self) -> Result<()> {
         let script = r#"
-        function clickPlayButton() {
-            const playButton = document.querySelector('.btn-play');
+        function clickPlayButton(chain) {
+            const playButton = pick(chain);
             if (!playButton) {
-                return "No play button found";
+                return failed('not_found', 'play button');
             }
             
             playButton.click();
-            return "Clicked on the play button";
+            return ok('clicked');
         }
-        return clickPlayButton();
+        return clickPlayButton(arguments[0]);
         "#;
 
-        let mut attempts = 0;
-        const MAX_ATTEMPTS: u32 = 5;
+        self.retry
+            .policy("movies.click_play_button")
+            .run_with_hook(
+                "click_play_button",
+                |attempt| self.try_click_play_button(script, attempt),
+                |attempt, _| async move {
+                    self.session
+                        .screenshot(&format!("play_button_attempt_{}_failed", attempt))
+                        .await
+                        .map(drop)
+                },
+            )
+            .await
+    }
 
-        while attempts < MAX_ATTEMPTS {
-            attempts += 1;
-            println!("Attempt {} to click the play button", attempts);
+    async fn try_click_play_button(&self, script: &str, attempt: u32) -> Result<()> {
+        println!("Attempt {} to click the play button", attempt);
 
-            self.take_screenshot(&format!(
-                "play_button_attempt_{}_before_click.png",
-                attempts
-            ))
+        self.session
+            .screenshot(&format!("play_button_attempt_{}_before_click", attempt))
             .await?;
 
-            let result = execute_script(&self.client, script).await?;
-
-            match result.as_str() {
-                Some("Clicked on the play button") => {
-                    println!("Successfully clicked the play button");
-
-                    // Wait for potential ad popups
-                    tokio::time::sleep(Duration::from_secs(5)).await;
-                    self.take_screenshot(&format!(
-                        "play_button_attempt_{}_after_click.png",
-                        attempts
-                    ))
-                    .await?;
-
-                    // Close any newly opened tabs except the main one
-                    self.close_other_tabs().await?;
-
-                    // Check if we're on the video player page
-                    if self.is_on_video_player_page().await? {
-                        self.take_screenshot(&format!(
-                            "play_button_attempt_{}_success.png",
-                            attempts
-                        ))
-                        .await?;
-                        return Ok(());
-                    }
-                }
-                Some("No play button found") => {
-                    println!("Play button not found, retrying...");
-                    self.take_screenshot(&format!(
-                        "play_button_attempt_{}_not_found.png",
-                        attempts
-                    ))
-                    .await?;
-                }
-                _ => {
-                    println!("Unexpected result when clicking the play button");
-                    self.take_screenshot(&format!(
-                        "play_button_attempt_{}_unexpected.png",
-                        attempts
-                    ))
-                    .await?;
-                }
-            }
-
-            tokio::time::sleep(Duration::from_secs(2)).await;
+        self.session
+            .run_step_with_args(
+                "click_play_button",
+                script,
+                vec![json!(self.selectors.get("play_button"))],
+            )
+            .await?;
+        println!("Successfully clicked the play button");
+
+        // Ad popups may open instead of the player; give the player
+        // a chance to show up before checking.
+        match self
+            .session
+            .wait()
+            .timeout(Duration::from_secs(10))
+            .for_visible(self.selectors.get("video_player"))
+            .await
+        {
+            Ok(()) | Err(ScrapeError::NavigationTimeout(_)) => {}
+            Err(e) => return Err(e),
         }
+        self.session
+            .screenshot(&format!("play_button_attempt_{}_after_click", attempt))
+            .await?;
 
-        Err(anyhow!(
-            "Failed to click the play button after {} attempts",
-            MAX_ATTEMPTS
-        ))
-    }
-
-    async fn close_other_tabs(&self) -> Result<()> {
-        let handles = self.client.windows().await?;
-        let current_handle = self.client.window().await?;
+        // Close any newly opened tabs except the main one
+        self.session.close_other_tabs().await?;
 
-        for handle in handles {
-            if handle != current_handle {
-                self.client.switch_to_window(handle).await?;
-                self.client.close_window().await?;
-            }
+        if !self.is_on_video_player_page().await? {
+            return Err(ScrapeError::UnexpectedPage(
+                "click_play_button: did not reach the video player".to_string(),
+            ));
         }
-
-        self.client.switch_to_window(current_handle).await?;
+        self.session
+            .screenshot(&format!("play_button_attempt_{}_success", attempt))
+            .await?;
         Ok(())
     }
 
@@ -269,29 +674,19 @@ impl MovieScraper {
     return checkMoviePage();
     "#;
 
-        let result = execute_script(&self.client, script).await?;
-        Ok(result.as_bool().unwrap_or(false))
+        self.session.execute(script).await
     }
     async fn is_on_video_player_page(&self) -> Result<bool> {
         let script = r#"
-        function checkVideoPlayerPage() {
+        function checkVideoPlayerPage(chain) {
             // Check for elements typically found on a video player page
-            const videoPlayer = document.querySelector('video');
-            const playerControls = document.querySelector('.jw-controls');
-            
-            return !!(videoPlayer || playerControls);
+            return !!pick(chain);
         }
-        return checkVideoPlayerPage();
+        return checkVideoPlayerPage(arguments[0]);
         "#;
 
-        let result = execute_script(&self.client, script).await?;
-        Ok(result.as_bool().unwrap_or(false))
-    }
-
-    async fn take_screenshot(&self, filename: &str) -> Result<()> {
-        let screenshot = self.client.screenshot().await?;
-        std::fs::write(filename, &screenshot)?;
-        println!("Screenshot saved as {}", filename);
-        Ok(())
+        self.session
+            .execute_with_args(script, vec![json!(self.selectors.get("video_player"))])
+            .await
     }
 }
@@ -0,0 +1,344 @@
+use crate::common::write_atomic;
+use crate::movie_info::MovieInfo;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashSet};
+use std::path::Path;
+
+/// Fields that describe where and when a card was seen rather than the movie
+/// itself, so changes to them are not history. Poster changes show up as
+/// changes to `poster_url`.
+const UNTRACKED_FIELDS: &[&str] = &["section", "page", "warnings", "poster"];
+
+/// Every movie seen across runs, keyed by [`MovieInfo::dedupe_key`].
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct MovieStore {
+    pub movies: BTreeMap<String, StoredMovie>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StoredMovie {
+    pub first_seen: DateTime<Local>,
+    pub last_seen: DateTime<Local>,
+    /// Whether the latest run that crawled this movie's section found it.
+    pub listed: bool,
+    pub movie: MovieInfo,
+    pub history: Vec<FieldChange>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FieldChange {
+    pub at: DateTime<Local>,
+    pub field: String,
+    pub old: Value,
+    pub new: Value,
+}
+
+/// What one run changed in the store.
+#[derive(Debug, Default, Serialize)]
+pub struct MovieDiff {
+    pub added: Vec<MovieRef>,
+    /// Listed before in a section this run crawled, but not found again.
+    pub removed: Vec<MovieRef>,
+    pub changed: Vec<ChangedMovie>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MovieRef {
+    pub id: String,
+    pub title: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ChangedMovie {
+    pub id: String,
+    pub title: String,
+    pub changes: Vec<FieldChange>,
+}
+
+impl MovieDiff {
+    pub fn summary(&self) -> String {
+        format!(
+            "{} new, {} removed, {} changed",
+            self.added.len(),
+            self.removed.len(),
+            self.changed.len()
+        )
+    }
+}
+
+impl MovieStore {
+    /// Loads the store at `path`, or an empty one if it does not exist yet.
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let json = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read movie store {}", path.display()))?;
+        serde_json::from_str(&json)
+            .with_context(|| format!("Failed to parse movie store {}", path.display()))
+    }
+
+    /// Writes to a temporary file first so an interrupted save keeps the
+    /// previous store intact.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        write_atomic(path, serde_json::to_string_pretty(self)?.as_bytes())
+            .with_context(|| format!("Failed to write movie store {}", path.display()))?;
+        Ok(())
+    }
+
+    /// Merges one run's movies into the store. `sections` are the sections
+    /// the run crawled to their last page; only movies last listed in one of
+    /// them can count as removed, since a movie missing from a partly
+    /// crawled section may just be on a page the run did not reach.
+    pub fn update(
+        &mut self,
+        movies: &[MovieInfo],
+        sections: &[&str],
+        now: DateTime<Local>,
+    ) -> Result<MovieDiff> {
+        let mut diff = MovieDiff::default();
+        let mut seen = HashSet::new();
+
+        for movie in movies {
+            let key = movie.dedupe_key();
+            seen.insert(key.clone());
+
+            let Some(stored) = self.movies.get_mut(&key) else {
+                diff.added.push(MovieRef {
+                    id: key.clone(),
+                    title: movie.title.clone(),
+                });
+                self.movies.insert(
+                    key,
+                    StoredMovie {
+                        first_seen: now,
+                        last_seen: now,
+                        listed: true,
+                        movie: movie.clone(),
+                        history: Vec::new(),
+                    },
+                );
+                continue;
+            };
+
+            let mut movie = movie.clone();
+            // A run without the detail pass says nothing about the details.
+            if movie.details.is_none() {
+                movie.details = stored.movie.details.clone();
+            }
+            let changes = field_changes(&stored.movie, &movie, now)?;
+            if !changes.is_empty() {
+                stored.history.extend(changes.iter().cloned());
+                diff.changed.push(ChangedMovie {
+                    id: key.clone(),
+                    title: movie.title.clone(),
+                    changes,
+                });
+            }
+            stored.movie = movie;
+            stored.last_seen = now;
+            stored.listed = true;
+        }
+
+        for (key, stored) in &mut self.movies {
+            if stored.listed
+                && !seen.contains(key)
+                && sections.contains(&stored.movie.section.as_str())
+            {
+                stored.listed = false;
+                diff.removed.push(MovieRef {
+                    id: key.clone(),
+                    title: stored.movie.title.clone(),
+                });
+            }
+        }
+
+        Ok(diff)
+    }
+}
+
+fn field_changes(
+    old: &MovieInfo,
+    new: &MovieInfo,
+    at: DateTime<Local>,
+) -> Result<Vec<FieldChange>> {
+    let (Value::Object(old), Value::Object(new)) =
+        (serde_json::to_value(old)?, serde_json::to_value(new)?)
+    else {
+        return Ok(Vec::new());
+    };
+    let fields: Vec<&String> = new
+        .keys()
+        .chain(old.keys().filter(|field| !new.contains_key(*field)))
+        .filter(|field| !UNTRACKED_FIELDS.contains(&field.as_str()))
+        .collect();
+
+    Ok(fields
+        .into_iter()
+        .filter_map(|field| {
+            let before = old.get(field).cloned().unwrap_or(Value::Null);
+            let after = new.get(field).cloned().unwrap_or(Value::Null);
+            (before != after).then(|| FieldChange {
+                at,
+                field: field.clone(),
+                old: before,
+                new: after,
+            })
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::movie_info::MovieDetails;
+    use chrono::TimeZone;
+    use serde_json::json;
+
+    fn movie(value: Value) -> MovieInfo {
+        serde_json::from_value(value).unwrap()
+    }
+
+    fn at(day: u32) -> DateTime<Local> {
+        Local.with_ymd_and_hms(2024, 3, day, 12, 0, 0).unwrap()
+    }
+
+    fn ids(refs: &[MovieRef]) -> Vec<&str> {
+        refs.iter().map(|r| r.id.as_str()).collect()
+    }
+
+    /// A store after one run that listed movies 1 and 2 in `home` and 3 in
+    /// `tv`.
+    fn store() -> MovieStore {
+        let mut store = MovieStore::default();
+        let diff = store
+            .update(
+                &[
+                    movie(json!({ "id": "1", "title": "Dune", "year": 2021, "section": "home" })),
+                    movie(json!({ "id": "2", "title": "Heat", "section": "home" })),
+                    movie(json!({ "id": "3", "title": "Severance", "section": "tv" })),
+                ],
+                &["home", "tv"],
+                at(1),
+            )
+            .unwrap();
+        assert_eq!(ids(&diff.added), ["1", "2", "3"]);
+        assert!(diff.removed.is_empty() && diff.changed.is_empty());
+        store
+    }
+
+    #[test]
+    fn reports_added_changed_and_removed_movies() {
+        let mut store = store();
+        let diff = store
+            .update(
+                &[
+                    movie(json!({ "id": "1", "title": "Dune", "year": 2022, "section": "home" })),
+                    movie(json!({ "id": "4", "title": "Alien", "section": "home" })),
+                    movie(json!({ "id": "3", "title": "Severance", "section": "tv" })),
+                ],
+                &["home", "tv"],
+                at(2),
+            )
+            .unwrap();
+        assert_eq!(ids(&diff.added), ["4"]);
+        assert_eq!(ids(&diff.removed), ["2"]);
+        assert_eq!(diff.changed.len(), 1);
+        assert_eq!(diff.changed[0].id, "1");
+        assert_eq!(diff.changed[0].changes.len(), 1);
+        let change = &diff.changed[0].changes[0];
+        assert_eq!(change.field, "year");
+        assert_eq!((&change.old, &change.new), (&json!(2021), &json!(2022)));
+        assert_eq!(change.at, at(2));
+
+        let dune = &store.movies["1"];
+        assert_eq!((dune.first_seen, dune.last_seen), (at(1), at(2)));
+        assert_eq!(dune.history.len(), 1);
+        assert!(!store.movies["2"].listed);
+        assert_eq!(store.movies["2"].last_seen, at(1));
+
+        // A removed movie is only reported once.
+        let diff = store.update(&[], &["home"], at(3)).unwrap();
+        assert_eq!(ids(&diff.removed), ["1", "4"]);
+    }
+
+    #[test]
+    fn untracked_fields_are_not_changes() {
+        let mut store = store();
+        let diff = store
+            .update(
+                &[movie(json!({
+                    "id": "1",
+                    "title": "Dune",
+                    "year": 2021,
+                    "section": "tv",
+                    "page": 3,
+                    "warnings": ["unrecognized info 'HD'"],
+                }))],
+                &[],
+                at(2),
+            )
+            .unwrap();
+        assert!(diff.changed.is_empty());
+        assert!(store.movies["1"].history.is_empty());
+        assert_eq!(store.movies["1"].movie.section, "tv");
+    }
+
+    #[test]
+    fn partly_crawled_sections_report_no_removals() {
+        let mut store = store();
+        // `home` was cut short by a limit, so only `tv` was crawled to the end.
+        let diff = store
+            .update(
+                &[movie(
+                    json!({ "id": "1", "title": "Dune", "year": 2021, "section": "home" }),
+                )],
+                &["tv"],
+                at(2),
+            )
+            .unwrap();
+        assert_eq!(ids(&diff.removed), ["3"]);
+        assert!(store.movies["2"].listed);
+    }
+
+    #[test]
+    fn movies_without_id_are_keyed_by_detail_url_then_title() {
+        let mut store = MovieStore::default();
+        let first = [
+            movie(json!({ "title": "Heat", "detail_url": "/movie/heat" })),
+            movie(json!({ "title": "Alien" })),
+        ];
+        store.update(&first, &[""], at(1)).unwrap();
+        assert!(store.movies.contains_key("/movie/heat"));
+        assert!(store.movies.contains_key("Alien"));
+
+        let diff = store
+            .update(
+                &[movie(json!({ "title": "Alien", "year": 1979 }))],
+                &[""],
+                at(2),
+            )
+            .unwrap();
+        assert!(diff.added.is_empty());
+        assert_eq!(diff.changed.len(), 1);
+        assert_eq!(diff.changed[0].id, "Alien");
+        assert_eq!(ids(&diff.removed), ["/movie/heat"]);
+    }
+
+    #[test]
+    fn a_run_without_details_keeps_the_stored_ones() {
+        let mut store = MovieStore::default();
+        let mut with_details = movie(json!({ "id": "1", "title": "Dune" }));
+        with_details.details = Some(MovieDetails::default());
+        store.update(&[with_details], &[], at(1)).unwrap();
+
+        let diff = store
+            .update(&[movie(json!({ "id": "1", "title": "Dune" }))], &[], at(2))
+            .unwrap();
+        assert!(diff.changed.is_empty());
+        assert!(store.movies["1"].movie.details.is_some());
+    }
+}
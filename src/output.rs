@@ -0,0 +1,477 @@
+use crate::debug_println;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    /// One pretty-printed JSON array, written when the run ends
+    #[default]
+    Json,
+    /// One JSON object per line, written as records arrive
+    Ndjson,
+    /// One row per record; nested values are written as JSON
+    Csv,
+    /// A table named after the scraper, one column per field
+    Sqlite,
+}
+
+impl OutputFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            OutputFormat::Json => "json",
+            OutputFormat::Ndjson => "ndjson",
+            OutputFormat::Csv => "csv",
+            OutputFormat::Sqlite => "sqlite",
+        }
+    }
+}
+
+/// The `[output]` config section.
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(default)]
+pub struct OutputConfig {
+    pub format: OutputFormat,
+}
+
+/// Somewhere records go. Records are JSON objects; a sink flattens them as
+/// far as its format needs.
+pub trait OutputSink: Send {
+    fn write(&mut self, record: &Value) -> Result<()>;
+
+    /// Flushes anything buffered. Called once, after the last record.
+    fn finish(self: Box<Self>) -> Result<()>;
+}
+
+/// Opens a sink of `format` at `path`; `name` is the SQLite table name.
+pub fn open_sink(format: OutputFormat, path: &Path, name: &str) -> Result<Box<dyn OutputSink>> {
+    Ok(match format {
+        OutputFormat::Json => Box::new(JsonSink::create(path)?),
+        OutputFormat::Ndjson => Box::new(NdjsonSink::create(path)?),
+        OutputFormat::Csv => Box::new(CsvSink::create(path)?),
+        OutputFormat::Sqlite => {
+            create_parent(path)?;
+            Box::new(SqliteSink::open(path, name)?)
+        }
+    })
+}
+
+/// The sink a scraper emits its records through, shared behind `&self`.
+pub struct Output {
+    sink: Mutex<Option<Box<dyn OutputSink>>>,
+    path: PathBuf,
+    count: Mutex<usize>,
+}
+
+impl Output {
+    pub fn new(sink: Box<dyn OutputSink>, path: PathBuf) -> Self {
+        Self {
+            sink: Mutex::new(Some(sink)),
+            path,
+            count: Mutex::new(0),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn emit<T: Serialize>(&self, record: &T) -> Result<()> {
+        let record = serde_json::to_value(record)?;
+        debug_println!("{}", record);
+        let mut sink = self.sink.lock().unwrap_or_else(|e| e.into_inner());
+        let sink = sink.as_mut().context("Output already finished")?;
+        sink.write(&record)
+            .with_context(|| format!("Failed to write to {}", self.path.display()))?;
+        *self.count.lock().unwrap_or_else(|e| e.into_inner()) += 1;
+        Ok(())
+    }
+
+    /// Finishes the sink, returning how many records were written.
+    pub fn finish(&self) -> Result<usize> {
+        let sink = self.sink.lock().unwrap_or_else(|e| e.into_inner()).take();
+        if let Some(sink) = sink {
+            sink.finish()
+                .with_context(|| format!("Failed to finish {}", self.path.display()))?;
+        }
+        Ok(*self.count.lock().unwrap_or_else(|e| e.into_inner()))
+    }
+}
+
+/// Buffers records and writes the file at the end, so a run that fails
+/// early does not leave a truncated array behind.
+struct JsonSink {
+    path: PathBuf,
+    records: Vec<Value>,
+}
+
+impl JsonSink {
+    fn create(path: &Path) -> Result<Self> {
+        create_parent(path)?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            records: Vec::new(),
+        })
+    }
+}
+
+impl OutputSink for JsonSink {
+    fn write(&mut self, record: &Value) -> Result<()> {
+        self.records.push(record.clone());
+        Ok(())
+    }
+
+    fn finish(self: Box<Self>) -> Result<()> {
+        let mut file = BufWriter::new(create_file(&self.path)?);
+        serde_json::to_writer_pretty(&mut file, &self.records)?;
+        file.flush()?;
+        Ok(())
+    }
+}
+
+struct NdjsonSink {
+    file: BufWriter<File>,
+}
+
+impl NdjsonSink {
+    fn create(path: &Path) -> Result<Self> {
+        Ok(Self {
+            file: BufWriter::new(create_file(path)?),
+        })
+    }
+}
+
+impl OutputSink for NdjsonSink {
+    fn write(&mut self, record: &Value) -> Result<()> {
+        serde_json::to_writer(&mut self.file, record)?;
+        self.file.write_all(b"\n")?;
+        // Flush per record so a crashed run still leaves every line it got to.
+        self.file.flush()?;
+        Ok(())
+    }
+
+    fn finish(mut self: Box<Self>) -> Result<()> {
+        self.file.flush()?;
+        Ok(())
+    }
+}
+
+/// Buffers rows until the end, since the header has to cover every field
+/// any record has.
+struct CsvSink {
+    path: PathBuf,
+    columns: Vec<String>,
+    rows: Vec<Value>,
+}
+
+impl CsvSink {
+    fn create(path: &Path) -> Result<Self> {
+        create_parent(path)?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            columns: Vec::new(),
+            rows: Vec::new(),
+        })
+    }
+}
+
+impl OutputSink for CsvSink {
+    fn write(&mut self, record: &Value) -> Result<()> {
+        for column in columns_of(record) {
+            if !self.columns.contains(&column) {
+                self.columns.push(column);
+            }
+        }
+        self.rows.push(record.clone());
+        Ok(())
+    }
+
+    fn finish(self: Box<Self>) -> Result<()> {
+        let mut writer = csv::Writer::from_writer(create_file(&self.path)?);
+        writer.write_record(&self.columns)?;
+        for row in &self.rows {
+            let cells = self
+                .columns
+                .iter()
+                .map(|column| cell_text(field(row, column)));
+            writer.write_record(cells)?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+/// Inserts each record as it arrives, adding columns the table does not
+/// have yet, so an existing database can be appended to across runs.
+struct SqliteSink {
+    conn: rusqlite::Connection,
+    table: String,
+    columns: Vec<String>,
+}
+
+impl SqliteSink {
+    fn open(path: &Path, table: &str) -> Result<Self> {
+        let conn = rusqlite::Connection::open(path)
+            .with_context(|| format!("Failed to open {}", path.display()))?;
+        let table = quote_ident(table);
+        conn.execute(
+            &format!(
+                "CREATE TABLE IF NOT EXISTS {} (scraped_at TEXT DEFAULT CURRENT_TIMESTAMP)",
+                table
+            ),
+            [],
+        )?;
+        let columns = {
+            let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+            let names = stmt.query_map([], |row| row.get::<_, String>(1))?;
+            names.collect::<rusqlite::Result<Vec<_>>>()?
+        };
+        Ok(Self {
+            conn,
+            table,
+            columns,
+        })
+    }
+}
+
+impl OutputSink for SqliteSink {
+    fn write(&mut self, record: &Value) -> Result<()> {
+        let columns = columns_of(record);
+        for column in &columns {
+            // SQLite column names ignore ASCII case, so `Title` is already
+            // there once `title` is.
+            if !self
+                .columns
+                .iter()
+                .any(|existing| existing.eq_ignore_ascii_case(column))
+            {
+                self.conn.execute(
+                    &format!(
+                        "ALTER TABLE {} ADD COLUMN {}",
+                        self.table,
+                        quote_ident(column)
+                    ),
+                    [],
+                )?;
+                self.columns.push(column.clone());
+            }
+        }
+
+        if columns.is_empty() {
+            self.conn
+                .execute(&format!("INSERT INTO {} DEFAULT VALUES", self.table), [])?;
+            return Ok(());
+        }
+        let names: Vec<String> = columns.iter().map(|c| quote_ident(c)).collect();
+        let placeholders = vec!["?"; columns.len()].join(", ");
+        let values: Vec<rusqlite::types::Value> = columns
+            .iter()
+            .map(|column| sql_value(field(record, column)))
+            .collect();
+        self.conn.execute(
+            &format!(
+                "INSERT INTO {} ({}) VALUES ({})",
+                self.table,
+                names.join(", "),
+                placeholders
+            ),
+            rusqlite::params_from_iter(values),
+        )?;
+        Ok(())
+    }
+
+    fn finish(self: Box<Self>) -> Result<()> {
+        self.conn.close().map_err(|(_, e)| e)?;
+        Ok(())
+    }
+}
+
+fn create_parent(path: &Path) -> Result<()> {
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    Ok(())
+}
+
+fn create_file(path: &Path) -> Result<File> {
+    create_parent(path)?;
+    File::create(path).with_context(|| format!("Failed to create {}", path.display()))
+}
+
+/// Top-level field names; a non-object record is a single `value` column.
+fn columns_of(record: &Value) -> Vec<String> {
+    match record {
+        Value::Object(map) => map.keys().cloned().collect(),
+        _ => vec!["value".to_string()],
+    }
+}
+
+fn field<'a>(record: &'a Value, column: &str) -> &'a Value {
+    match record {
+        Value::Object(map) => map.get(column).unwrap_or(&Value::Null),
+        other if column == "value" => other,
+        _ => &Value::Null,
+    }
+}
+
+fn cell_text(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+fn sql_value(value: &Value) -> rusqlite::types::Value {
+    use rusqlite::types::Value as Sql;
+    match value {
+        Value::Null => Sql::Null,
+        Value::Bool(b) => Sql::Integer(*b as i64),
+        Value::Number(n) => match n.as_i64() {
+            Some(i) => Sql::Integer(i),
+            None => Sql::Real(n.as_f64().unwrap_or_default()),
+        },
+        Value::String(s) => Sql::Text(s.clone()),
+        other => Sql::Text(other.to_string()),
+    }
+}
+
+fn quote_ident(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// A fresh directory for one test's output.
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("rwa-output-test-{}-{}", std::process::id(), name));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn write_all(format: OutputFormat, path: &Path, records: &[Value]) {
+        let output = Output::new(open_sink(format, path, "movies").unwrap(), path.into());
+        for record in records {
+            output.emit(record).unwrap();
+        }
+        assert_eq!(output.finish().unwrap(), records.len());
+    }
+
+    #[test]
+    fn json_writes_a_pretty_array_on_finish() {
+        let dir = temp_dir("json");
+        let path = dir.join("out/movies.json");
+        let mut sink = open_sink(OutputFormat::Json, &path, "movies").unwrap();
+        sink.write(&json!({ "title": "Dune" })).unwrap();
+        sink.write(&json!({ "title": "Heat" })).unwrap();
+        assert!(!path.exists());
+        sink.finish().unwrap();
+
+        let text = std::fs::read_to_string(&path).unwrap();
+        assert!(text.starts_with("[\n  {"));
+        let records: Value = serde_json::from_str(&text).unwrap();
+        assert_eq!(records, json!([{ "title": "Dune" }, { "title": "Heat" }]));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn ndjson_writes_one_line_per_record() {
+        let dir = temp_dir("ndjson");
+        let path = dir.join("movies.ndjson");
+        write_all(
+            OutputFormat::Ndjson,
+            &path,
+            &[json!({ "title": "Dune", "year": 2021 }), json!("bare")],
+        );
+
+        let text = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(text, "{\"title\":\"Dune\",\"year\":2021}\n\"bare\"\n");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn csv_header_covers_every_field_and_leaves_missing_cells_empty() {
+        let dir = temp_dir("csv");
+        let path = dir.join("movies.csv");
+        write_all(
+            OutputFormat::Csv,
+            &path,
+            &[
+                json!({ "title": "Dune", "year": 2021 }),
+                json!({ "rating": 8.1, "title": "Heat", "tags": ["crime"] }),
+            ],
+        );
+
+        let text = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(
+            text,
+            "title,year,rating,tags\nDune,2021,,\nHeat,,8.1,\"[\"\"crime\"\"]\"\n"
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn sqlite_adds_columns_to_an_existing_table() {
+        let dir = temp_dir("sqlite");
+        let path = dir.join("movies.sqlite");
+        write_all(OutputFormat::Sqlite, &path, &[json!({ "title": "Dune" })]);
+        write_all(
+            OutputFormat::Sqlite,
+            &path,
+            &[
+                json!({ "title": "Heat", "year": 1995 }),
+                json!({ "Title": "Alien" }),
+                json!({}),
+                json!(42),
+            ],
+        );
+
+        let conn = rusqlite::Connection::open(&path).unwrap();
+        let columns: Vec<String> = conn
+            .prepare("PRAGMA table_info(movies)")
+            .unwrap()
+            .query_map([], |row| row.get(1))
+            .unwrap()
+            .collect::<rusqlite::Result<_>>()
+            .unwrap();
+        assert_eq!(columns, ["scraped_at", "title", "year", "value"]);
+
+        let rows: Vec<(Option<String>, Option<i64>, Option<i64>)> = conn
+            .prepare("SELECT title, year, value FROM movies ORDER BY rowid")
+            .unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+            .unwrap()
+            .collect::<rusqlite::Result<_>>()
+            .unwrap();
+        assert_eq!(
+            rows,
+            [
+                (Some("Dune".to_string()), None, None),
+                (Some("Heat".to_string()), Some(1995), None),
+                (Some("Alien".to_string()), None, None),
+                (None, None, None),
+                (None, None, Some(42)),
+            ]
+        );
+        let stamped: i64 = conn
+            .query_row(
+                "SELECT count(*) FROM movies WHERE scraped_at IS NOT NULL",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(stamped, 5);
+        drop(conn);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
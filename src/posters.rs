@@ -0,0 +1,228 @@
+use crate::artifacts::{ArtifactKind, RunArtifacts};
+use crate::common::write_atomic;
+use anyhow::{bail, Context, Result};
+use image::imageops::FilterType;
+use image::{DynamicImage, ImageFormat};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::io::Cursor;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Larger responses are not posters.
+const MAX_POSTER_BYTES: usize = 10 * 1024 * 1024;
+
+/// A downloaded poster, stored in the run's `posters/` directory under its
+/// SHA-256 so the same image fetched twice is written once.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PosterImage {
+    /// Relative to the run directory.
+    pub path: PathBuf,
+    pub thumbnail: PathBuf,
+    pub sha256: String,
+    pub width: u32,
+    pub height: u32,
+    /// 64-bit difference hash as hex; near-identical images differ in few bits.
+    pub dhash: String,
+}
+
+pub struct PosterDownloader<'a> {
+    client: reqwest::Client,
+    artifacts: &'a RunArtifacts,
+    thumbnail_size: u32,
+    /// Hashes of the posters already written by this downloader.
+    written: Mutex<HashSet<String>>,
+}
+
+impl<'a> PosterDownloader<'a> {
+    pub fn new(artifacts: &'a RunArtifacts, thumbnail_size: u32) -> Result<Self> {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(30))
+            .build()?;
+        Ok(Self {
+            client,
+            artifacts,
+            thumbnail_size,
+            written: Mutex::new(HashSet::new()),
+        })
+    }
+
+    pub async fn fetch(&self, url: &str) -> Result<PosterImage> {
+        let response = self
+            .client
+            .get(url)
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .with_context(|| format!("Failed to download {}", url))?;
+        if response
+            .content_length()
+            .is_some_and(|len| len as usize > MAX_POSTER_BYTES)
+        {
+            bail!("{} is larger than {} bytes", url, MAX_POSTER_BYTES);
+        }
+        let bytes = response.bytes().await?;
+        if bytes.len() > MAX_POSTER_BYTES {
+            bail!("{} is larger than {} bytes", url, MAX_POSTER_BYTES);
+        }
+
+        let sha256 = format!("{:x}", Sha256::digest(&bytes));
+        let thumbnail_size = self.thumbnail_size;
+        let decoded = bytes.clone();
+        let (format, image, thumbnail) = tokio::task::spawn_blocking(move || {
+            let format = image::guess_format(&decoded)?;
+            let image = image::load_from_memory_with_format(&decoded, format)?;
+            let mut thumbnail = Vec::new();
+            image
+                .thumbnail(thumbnail_size, thumbnail_size)
+                .write_to(&mut Cursor::new(&mut thumbnail), ImageFormat::Png)?;
+            Ok::<_, image::ImageError>((format, image, thumbnail))
+        })
+        .await?
+        .with_context(|| format!("{} is not a valid image", url))?;
+
+        let extension = format.extensions_str().first().copied().unwrap_or("img");
+        let path = self
+            .artifacts
+            .path(ArtifactKind::Poster, &format!("{}.{}", sha256, extension));
+        let thumbnail_path = self
+            .artifacts
+            .path(ArtifactKind::Poster, &format!("thumbs/{}.png", sha256));
+        // Claimed before writing so that concurrent downloads of the same
+        // image write and record it once.
+        let first = self
+            .written
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(sha256.clone());
+        if first && !path.exists() {
+            write_atomic(&path, &bytes)
+                .with_context(|| format!("Failed to write poster {}", path.display()))?;
+            write_atomic(&thumbnail_path, &thumbnail).with_context(|| {
+                format!("Failed to write thumbnail {}", thumbnail_path.display())
+            })?;
+            self.artifacts
+                .record(ArtifactKind::Poster, &path, "download_posters")?;
+            self.artifacts
+                .record(ArtifactKind::Poster, &thumbnail_path, "download_posters")?;
+        }
+
+        let relative = |p: PathBuf| {
+            p.strip_prefix(self.artifacts.root())
+                .map(|p| p.to_path_buf())
+                .unwrap_or(p)
+        };
+        Ok(PosterImage {
+            path: relative(path),
+            thumbnail: relative(thumbnail_path),
+            sha256,
+            width: image.width(),
+            height: image.height(),
+            dhash: format!("{:016x}", dhash(&image)),
+        })
+    }
+}
+
+/// Difference hash: shrink to 9x8 grayscale and set one bit per pixel that
+/// is brighter than its right-hand neighbour.
+fn dhash(image: &DynamicImage) -> u64 {
+    let small = image::imageops::resize(&image.to_luma8(), 9, 8, FilterType::Triangle);
+    let mut hash = 0u64;
+    for y in 0..8 {
+        for x in 0..8 {
+            let left = small.get_pixel(x, y)[0];
+            let right = small.get_pixel(x + 1, y)[0];
+            hash = (hash << 1) | u64::from(left > right);
+        }
+    }
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{GrayImage, Luma};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// A 36x16 grayscale PNG getting darker from left to right, so every
+    /// dHash bit is set.
+    fn gradient_png() -> Vec<u8> {
+        let image = GrayImage::from_fn(36, 16, |x, _| Luma([255 - (x * 7) as u8]));
+        let mut png = Vec::new();
+        DynamicImage::ImageLuma8(image)
+            .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+            .unwrap();
+        png
+    }
+
+    /// Serves `/poster.png` and `/page.html` over plain HTTP/1.1.
+    async fn serve(png: Vec<u8>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let png = png.clone();
+                tokio::spawn(async move {
+                    let mut request = vec![0u8; 4096];
+                    let n = stream.read(&mut request).await.unwrap_or(0);
+                    let request = String::from_utf8_lossy(&request[..n]);
+                    let (content_type, body) = if request.starts_with("GET /poster.png ") {
+                        ("image/png", png)
+                    } else {
+                        (
+                            "text/html",
+                            b"<html><body>Not a poster</body></html>".to_vec(),
+                        )
+                    };
+                    let head = format!(
+                        "HTTP/1.1 200 OK\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                        content_type,
+                        body.len()
+                    );
+                    let _ = stream.write_all(head.as_bytes()).await;
+                    let _ = stream.write_all(&body).await;
+                });
+            }
+        });
+        format!("http://{}", addr)
+    }
+
+    #[tokio::test]
+    async fn fetch_stores_original_bytes_and_rejects_non_images() {
+        let png = gradient_png();
+        let base = serve(png.clone()).await;
+        let dir = std::env::temp_dir().join(format!("rwa-posters-test-{}", std::process::id()));
+        let artifacts = RunArtifacts::create(&dir, "movies").unwrap();
+        let downloader = PosterDownloader::new(&artifacts, 8).unwrap();
+
+        let poster = downloader
+            .fetch(&format!("{}/poster.png", base))
+            .await
+            .unwrap();
+        let sha256 = format!("{:x}", Sha256::digest(&png));
+        assert_eq!(poster.sha256, sha256);
+        assert_eq!(
+            poster.path,
+            PathBuf::from(format!("posters/{}.png", sha256))
+        );
+        assert_eq!(
+            std::fs::read(artifacts.root().join(&poster.path)).unwrap(),
+            png
+        );
+        assert_eq!((poster.width, poster.height), (36, 16));
+        assert_eq!(poster.dhash, "ffffffffffffffff");
+        let thumbnail = image::open(artifacts.root().join(&poster.thumbnail)).unwrap();
+        assert!(thumbnail.width() <= 8 && thumbnail.height() <= 8);
+
+        let error = downloader
+            .fetch(&format!("{}/page.html", base))
+            .await
+            .unwrap_err();
+        assert!(format!("{:#}", error).contains("is not a valid image"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,288 @@
+use crate::error::{ErrorKind, Result, ScrapeError};
+use rand::Rng;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::future::Future;
+use std::time::Duration;
+
+/// How often and how patiently to retry a failing step.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct RetryPolicy {
+    /// Total attempts, including the first.
+    pub max_attempts: u32,
+    pub initial_delay_ms: u64,
+    pub max_delay_ms: u64,
+    /// Factor the delay grows by after each failed attempt.
+    pub multiplier: f64,
+    /// Fraction of the delay randomly added or removed, from 0.0 to 1.0.
+    pub jitter: f64,
+    /// Failures worth another attempt; anything else fails immediately.
+    pub retry_on: Vec<ErrorKind>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_delay_ms: 1000,
+            max_delay_ms: 30_000,
+            multiplier: 2.0,
+            jitter: 0.2,
+            retry_on: vec![
+                ErrorKind::Connection,
+                ErrorKind::NavigationTimeout,
+                ErrorKind::SelectorMissing,
+                ErrorKind::Script,
+                ErrorKind::UnexpectedPage,
+                ErrorKind::RateLimited,
+            ],
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn is_retryable(&self, error: &ScrapeError) -> bool {
+        self.retry_on.contains(&error.kind())
+    }
+
+    /// Delay before attempt `attempt + 1`, after `attempt` failures. Never
+    /// more than `max_delay_ms`, jitter included.
+    pub fn delay_after(&self, attempt: u32) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(i32::MAX as u32) as i32;
+        let max = self.max_delay_ms as f64;
+        let base = (self.initial_delay_ms as f64 * self.multiplier.powi(exponent)).min(max);
+        let jitter = self.jitter.clamp(0.0, 1.0);
+        let factor = if jitter > 0.0 {
+            rand::thread_rng().gen_range(1.0 - jitter..=1.0 + jitter)
+        } else {
+            1.0
+        };
+        Duration::from_millis((base * factor).min(max) as u64)
+    }
+
+    /// Runs `op` until it succeeds, fails with a non-retryable error, or runs
+    /// out of attempts. `op` receives the 1-based attempt number.
+    pub async fn run<T, F, Fut>(&self, step: &str, op: F) -> Result<T>
+    where
+        F: FnMut(u32) -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        self.run_with_hook(step, op, |_, _| async { Ok(()) }).await
+    }
+
+    /// Like [`run`](Self::run), calling `on_retry` with the attempt number and
+    /// error before each retry, e.g. to capture a screenshot or dismiss a popup.
+    /// A failing hook is logged and the retry goes ahead, so a recovery that
+    /// does not work cannot hide the failure that called for it.
+    pub async fn run_with_hook<T, F, Fut, H, HFut>(
+        &self,
+        step: &str,
+        mut op: F,
+        mut on_retry: H,
+    ) -> Result<T>
+    where
+        F: FnMut(u32) -> Fut,
+        Fut: Future<Output = Result<T>>,
+        H: FnMut(u32, ScrapeError) -> HFut,
+        HFut: Future<Output = Result<()>>,
+    {
+        let max_attempts = self.max_attempts.max(1);
+        let mut attempt = 1;
+        loop {
+            match op(attempt).await {
+                Ok(value) => return Ok(value),
+                Err(e) if attempt < max_attempts && self.is_retryable(&e) => {
+                    let delay = self.delay_after(attempt);
+                    println!(
+                        "{}: attempt {} of {} failed: {}. Retrying in {:?}...",
+                        step, attempt, max_attempts, e, delay
+                    );
+                    if let Err(hook_error) = on_retry(attempt, e).await {
+                        println!(
+                            "{}: preparing attempt {} failed: {}",
+                            step,
+                            attempt + 1,
+                            hook_error
+                        );
+                    }
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+/// A step's `[retry.steps."<step>"]` table; unset fields fall back to
+/// `[retry.default]`.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct RetryOverrides {
+    pub max_attempts: Option<u32>,
+    pub initial_delay_ms: Option<u64>,
+    pub max_delay_ms: Option<u64>,
+    pub multiplier: Option<f64>,
+    pub jitter: Option<f64>,
+    pub retry_on: Option<Vec<ErrorKind>>,
+}
+
+impl RetryOverrides {
+    fn apply(&self, base: &RetryPolicy) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: self.max_attempts.unwrap_or(base.max_attempts),
+            initial_delay_ms: self.initial_delay_ms.unwrap_or(base.initial_delay_ms),
+            max_delay_ms: self.max_delay_ms.unwrap_or(base.max_delay_ms),
+            multiplier: self.multiplier.unwrap_or(base.multiplier),
+            jitter: self.jitter.unwrap_or(base.jitter),
+            retry_on: self.retry_on.clone().unwrap_or(base.retry_on.clone()),
+        }
+    }
+}
+
+/// The `[retry]` config section.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct RetryConfig {
+    pub default: RetryPolicy,
+    /// Keyed by step name, e.g. `connect` or `movies.click_play_button`.
+    pub steps: HashMap<String, RetryOverrides>,
+}
+
+impl RetryConfig {
+    pub fn policy(&self, step: &str) -> RetryPolicy {
+        match self.steps.get(step) {
+            Some(overrides) => overrides.apply(&self.default),
+            None => builtin_overrides(step).apply(&self.default),
+        }
+    }
+}
+
+/// Step defaults that differ from `[retry.default]` unless configured.
+fn builtin_overrides(step: &str) -> RetryOverrides {
+    match step {
+        "connect" => RetryOverrides {
+            max_attempts: Some(5),
+            initial_delay_ms: Some(2000),
+            multiplier: Some(1.0),
+            ..RetryOverrides::default()
+        },
+        "movies.click_first_movie" | "movies.click_play_button" => RetryOverrides {
+            max_attempts: Some(5),
+            ..RetryOverrides::default()
+        },
+        _ => RetryOverrides::default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    fn backoff(jitter: f64) -> RetryPolicy {
+        RetryPolicy {
+            initial_delay_ms: 1000,
+            max_delay_ms: 5000,
+            multiplier: 2.0,
+            jitter,
+            ..RetryPolicy::default()
+        }
+    }
+
+    #[test]
+    fn delay_grows_by_the_multiplier_up_to_the_cap() {
+        let policy = backoff(0.0);
+        let delays: Vec<u64> = (1..=5)
+            .map(|attempt| policy.delay_after(attempt).as_millis() as u64)
+            .collect();
+        assert_eq!(delays, [1000, 2000, 4000, 5000, 5000]);
+        assert_eq!(policy.delay_after(u32::MAX), Duration::from_millis(5000));
+    }
+
+    #[test]
+    fn jitter_stays_within_its_fraction_and_under_the_cap() {
+        let policy = backoff(0.2);
+        for _ in 0..200 {
+            let first = policy.delay_after(1).as_millis();
+            assert!((800..=1200).contains(&first), "{}", first);
+            let capped = policy.delay_after(4).as_millis();
+            assert!((4000..=5000).contains(&capped), "{}", capped);
+        }
+
+        // Jitter above 1.0 counts as 1.0, at most doubling the delay.
+        let wild = backoff(5.0);
+        for _ in 0..200 {
+            assert!(wild.delay_after(1) <= Duration::from_millis(2000));
+        }
+    }
+
+    #[test]
+    fn step_overrides_replace_only_the_fields_they_set() {
+        let config: RetryConfig = toml::from_str(
+            r#"
+            [default]
+            max_attempts = 4
+            jitter = 0.0
+
+            [steps."jobs.iteration"]
+            max_attempts = 2
+            retry_on = ["connection"]
+            "#,
+        )
+        .unwrap();
+
+        let step = config.policy("jobs.iteration");
+        assert_eq!(step.max_attempts, 2);
+        assert_eq!(step.retry_on, [ErrorKind::Connection]);
+        assert_eq!(step.jitter, 0.0);
+        assert_eq!(step.initial_delay_ms, 1000);
+
+        let other = config.policy("movies.extract");
+        assert_eq!(other.max_attempts, 4);
+        assert_eq!(other.retry_on, RetryPolicy::default().retry_on);
+
+        // Built-in step defaults give way to the configured default only
+        // where they set nothing.
+        let connect = config.policy("connect");
+        assert_eq!(connect.max_attempts, 5);
+        assert_eq!(connect.jitter, 0.0);
+    }
+
+    #[tokio::test]
+    async fn a_failing_hook_does_not_stop_the_retry() {
+        let policy = RetryPolicy {
+            initial_delay_ms: 0,
+            jitter: 0.0,
+            ..RetryPolicy::default()
+        };
+        let hooks = Cell::new(0);
+        let result = policy
+            .run_with_hook(
+                "test",
+                |attempt| async move {
+                    if attempt < 3 {
+                        Err(ScrapeError::Connection("refused".to_string()))
+                    } else {
+                        Ok(attempt)
+                    }
+                },
+                |_, _| {
+                    hooks.set(hooks.get() + 1);
+                    async { Err(ScrapeError::Script("no popup to close".to_string())) }
+                },
+            )
+            .await;
+        assert_eq!(result.unwrap(), 3);
+        assert_eq!(hooks.get(), 2);
+
+        let result: Result<()> = policy
+            .run_with_hook(
+                "test",
+                |_| async { Err(ScrapeError::Connection("refused".to_string())) },
+                |_, _| async { Err(ScrapeError::Script("no popup to close".to_string())) },
+            )
+            .await;
+        assert!(matches!(result, Err(ScrapeError::Connection(_))));
+    }
+}
@@ -0,0 +1,72 @@
+use crate::common::{BrowserSession, SessionFactory};
+use crate::error::{Result, ScrapeError};
+use crate::job_scraper::JobScraper;
+use crate::movie_scraper::MovieScraper;
+use crate::output::Output;
+use crate::retry::RetryConfig;
+use async_trait::async_trait;
+use serde::de::DeserializeOwned;
+
+/// Every scraper the binary knows about. Adding a site means adding its
+/// module and one line here.
+pub fn registry() -> Vec<Box<dyn Scraper>> {
+    vec![Box::new(JobScraper), Box::new(MovieScraper)]
+}
+
+pub fn find<'a>(registry: &'a [Box<dyn Scraper>], name: &str) -> Option<&'a dyn Scraper> {
+    registry
+        .iter()
+        .find(|scraper| scraper.name() == name)
+        .map(|scraper| scraper.as_ref())
+}
+
+#[async_trait]
+pub trait Scraper: Send + Sync {
+    /// Subcommand name, also the key of its `[scrapers.<name>]` config table.
+    fn name(&self) -> &'static str;
+
+    fn description(&self) -> &'static str;
+
+    /// Settings read from the scraper's config table.
+    fn config_schema(&self) -> Vec<ConfigField> {
+        Vec::new()
+    }
+
+    async fn run(&self, ctx: &ScrapeContext) -> Result<ScrapeReport>;
+}
+
+pub struct ConfigField {
+    pub key: &'static str,
+    pub description: &'static str,
+    pub default: &'static str,
+}
+
+/// Everything a scraper needs for one run.
+pub struct ScrapeContext {
+    pub session: BrowserSession,
+    /// Opens additional sessions for work that runs in parallel.
+    pub sessions: SessionFactory,
+    pub settings: toml::Table,
+    /// Where the scraper emits its records.
+    pub output: Output,
+    /// Retry policies, looked up by step name with [`RetryConfig::policy`].
+    pub retry: RetryConfig,
+}
+
+impl ScrapeContext {
+    /// Reads `key` from the scraper's config table, falling back to `default`.
+    pub fn setting<T: DeserializeOwned>(&self, key: &str, default: T) -> Result<T> {
+        match self.settings.get(key) {
+            Some(value) => value
+                .clone()
+                .try_into()
+                .map_err(|e| ScrapeError::Config(format!("invalid value for '{}': {}", key, e))),
+            None => Ok(default),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct ScrapeReport {
+    pub summary: String,
+}
@@ -0,0 +1,107 @@
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+
+/// Defines `pick(chain, root)` and `pickAll(chain, root)`, which try the
+/// selectors of a chain in order and return the first element, or every
+/// element, matching the first selector that matches anything. Prepended to
+/// every script the session runs.
+pub const SELECTOR_JS: &str = r#"
+const pick = (chain, root = document) => {
+    for (const selector of chain) {
+        const el = root.querySelector(selector);
+        if (el) return el;
+    }
+    return null;
+};
+const pickAll = (chain, root = document) => {
+    for (const selector of chain) {
+        const els = root.querySelectorAll(selector);
+        if (els.length) return Array.from(els);
+    }
+    return [];
+};
+"#;
+
+/// The CSS selectors for one logical element, tried in order until one
+/// matches. Serializes to a JSON array for `pick` and `pickAll`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct SelectorChain(Vec<String>);
+
+impl SelectorChain {
+    pub fn iter(&self) -> impl Iterator<Item = &str> {
+        self.0.iter().map(String::as_str)
+    }
+}
+
+impl fmt::Display for SelectorChain {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let quoted: Vec<String> = self.0.iter().map(|s| format!("{:?}", s)).collect();
+        f.write_str(&quoted.join(" or "))
+    }
+}
+
+/// A site's selectors by element name: the built-in catalog, with any
+/// entries from an override file replacing the built-in ones.
+#[derive(Debug)]
+pub struct SelectorCatalog {
+    site: String,
+    chains: BTreeMap<String, SelectorChain>,
+}
+
+impl SelectorCatalog {
+    /// Parses the built-in catalog `defaults` of `site` and applies the
+    /// override file, if given. Overrides may only name elements the
+    /// built-in catalog has, so a typo is an error rather than ignored.
+    pub fn load(site: &str, defaults: &str, overrides: Option<&Path>) -> Result<Self> {
+        let mut chains: BTreeMap<String, SelectorChain> = toml::from_str(defaults)
+            .with_context(|| format!("Failed to parse the built-in {} selectors", site))?;
+
+        if let Some(path) = overrides {
+            let text = std::fs::read_to_string(path)
+                .with_context(|| format!("Failed to read selectors file {}", path.display()))?;
+            let replaced: BTreeMap<String, SelectorChain> = toml::from_str(&text)
+                .with_context(|| format!("Failed to parse selectors file {}", path.display()))?;
+            for (name, chain) in replaced {
+                if !chains.contains_key(&name) {
+                    bail!("Unknown {} selector '{}' in {}", site, name, path.display());
+                }
+                if chain.0.is_empty() {
+                    bail!("Selector '{}' in {} is empty", name, path.display());
+                }
+                chains.insert(name, chain);
+            }
+            println!("Loaded {} selectors from {}", site, path.display());
+        }
+
+        Ok(Self {
+            site: site.to_string(),
+            chains,
+        })
+    }
+
+    /// The chain for `name`. Names come from the scraper's code and the
+    /// built-in catalog always has them, so a miss is a bug.
+    pub fn get(&self, name: &str) -> &SelectorChain {
+        self.chains.get(name).unwrap_or_else(|| {
+            panic!(
+                "selector '{}' is missing from the built-in {} catalog",
+                name, self.site
+            )
+        })
+    }
+
+    /// The chains for `names` as a JSON object keyed by name, to pass to a
+    /// script.
+    pub fn json(&self, names: &[&str]) -> Value {
+        names
+            .iter()
+            .map(|name| (name.to_string(), serde_json::json!(self.get(name))))
+            .collect::<serde_json::Map<_, _>>()
+            .into()
+    }
+}
@@ -0,0 +1,335 @@
+use crate::common::write_atomic_private;
+use aes_gcm::aead::{Aead, KeyInit, Payload};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use anyhow::{anyhow, bail, Context, Result};
+use argon2::Argon2;
+use chrono::{DateTime, Duration, Local};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Written first in every session file and authenticated with it, so a file
+/// from another format version fails to decrypt instead of misparsing.
+const MAGIC: &[u8] = b"rwa-session-v1\n";
+/// Follows the magic; a passphrase key is derived with it, so the same
+/// passphrase gives every file its own key.
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+const KEY_FILE: &str = "session.key";
+/// A passphrase here replaces the generated key file.
+const KEY_ENV: &str = "SCRAPER_SESSION_KEY";
+
+/// The `[session]` config section.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct SessionConfig {
+    /// Whether to save the browser's cookies and localStorage after a run
+    /// and restore them at the start of the next.
+    pub persist: bool,
+    /// Holds one encrypted file per scraper and the key.
+    pub dir: PathBuf,
+    /// Saved sessions older than this are discarded instead of restored.
+    pub max_age_days: u32,
+}
+
+impl Default for SessionConfig {
+    fn default() -> Self {
+        Self {
+            persist: true,
+            dir: PathBuf::from("sessions"),
+            max_age_days: 14,
+        }
+    }
+}
+
+/// Browser state worth keeping between runs: the cookies and localStorage
+/// of the site the run ended on.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SavedSession {
+    pub saved_at: DateTime<Local>,
+    /// Origin the state belongs to, e.g. `https://www.linkedin.com`.
+    pub origin: String,
+    pub cookies: Vec<SavedCookie>,
+    pub local_storage: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SavedCookie {
+    pub name: String,
+    pub value: String,
+    pub domain: Option<String>,
+    pub path: Option<String>,
+    pub secure: bool,
+    pub http_only: bool,
+    /// Unix timestamp; `None` for session cookies.
+    pub expires: Option<i64>,
+    pub same_site: Option<String>,
+}
+
+/// One scraper's saved session, encrypted with AES-256-GCM.
+pub struct SessionStore {
+    path: PathBuf,
+    key_path: PathBuf,
+    /// From `$SCRAPER_SESSION_KEY`; replaces the key file when set.
+    passphrase: Option<String>,
+    max_age: Duration,
+}
+
+impl SessionStore {
+    pub fn new(config: &SessionConfig, scraper: &str) -> Self {
+        Self {
+            path: session_path(&config.dir, scraper),
+            key_path: config.dir.join(KEY_FILE),
+            passphrase: std::env::var(KEY_ENV).ok(),
+            max_age: Duration::days(config.max_age_days.into()),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The saved session, or `None` if there is none or it is too old to
+    /// trust. Cookies that have expired since are dropped.
+    pub fn load(&self) -> Result<Option<SavedSession>> {
+        if !self.path.exists() {
+            return Ok(None);
+        }
+        let bytes = std::fs::read(&self.path)
+            .with_context(|| format!("Failed to read saved session {}", self.path.display()))?;
+        let json = self.decrypt(&bytes).with_context(|| {
+            format!(
+                "Failed to decrypt saved session {}; clear it with `session clear`",
+                self.path.display()
+            )
+        })?;
+        let mut session: SavedSession = serde_json::from_slice(&json)?;
+
+        let now = Local::now();
+        if now - session.saved_at > self.max_age {
+            println!(
+                "Saved session from {} is older than {} days; starting fresh",
+                session.saved_at.format("%Y-%m-%d"),
+                self.max_age.num_days()
+            );
+            std::fs::remove_file(&self.path)?;
+            return Ok(None);
+        }
+        session
+            .cookies
+            .retain(|cookie| cookie.expires.is_none_or(|at| at > now.timestamp()));
+        Ok(Some(session))
+    }
+
+    /// Encrypts and writes `session`, readable only by the current user.
+    pub fn save(&self, session: &SavedSession) -> Result<()> {
+        let json = serde_json::to_vec(session)?;
+        let bytes = self.encrypt(&json)?;
+        write_atomic_private(&self.path, &bytes)
+            .with_context(|| format!("Failed to write saved session {}", self.path.display()))
+    }
+
+    /// Lays the file out as magic, salt, nonce and ciphertext, with the
+    /// magic and salt authenticated alongside the ciphertext.
+    fn encrypt(&self, plaintext: &[u8]) -> Result<Vec<u8>> {
+        let salt: [u8; SALT_LEN] = rand::random();
+        let nonce: [u8; NONCE_LEN] = rand::random();
+        let header = [MAGIC, &salt].concat();
+        let cipher = Aes256Gcm::new(&self.key(&salt, true)?);
+        let ciphertext = cipher
+            .encrypt(
+                Nonce::from_slice(&nonce),
+                Payload {
+                    msg: plaintext,
+                    aad: &header,
+                },
+            )
+            .map_err(|_| anyhow!("encryption failed"))?;
+        Ok([&header, &nonce[..], &ciphertext].concat())
+    }
+
+    fn decrypt(&self, bytes: &[u8]) -> Result<Vec<u8>> {
+        let rest = bytes
+            .strip_prefix(MAGIC)
+            .ok_or_else(|| anyhow!("not a saved session file"))?;
+        if rest.len() < SALT_LEN + NONCE_LEN {
+            bail!("file is truncated");
+        }
+        let (header, rest) = bytes.split_at(MAGIC.len() + SALT_LEN);
+        let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+        let cipher = Aes256Gcm::new(&self.key(&header[MAGIC.len()..], false)?);
+        cipher
+            .decrypt(
+                Nonce::from_slice(nonce),
+                Payload {
+                    msg: ciphertext,
+                    aad: header,
+                },
+            )
+            .map_err(|_| anyhow!("wrong key or corrupted file"))
+    }
+
+    /// The key derived with Argon2id from `$SCRAPER_SESSION_KEY` and the
+    /// file's `salt`, or else the key file, which is generated on first
+    /// save.
+    fn key(&self, salt: &[u8], create: bool) -> Result<Key<Aes256Gcm>> {
+        if let Some(passphrase) = &self.passphrase {
+            let mut key = Key::<Aes256Gcm>::default();
+            Argon2::default()
+                .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+                .map_err(|e| anyhow!("failed to derive the session key: {}", e))?;
+            return Ok(key);
+        }
+        if !self.key_path.exists() {
+            if !create {
+                bail!("key file {} is missing", self.key_path.display());
+            }
+            let key: [u8; 32] = rand::random();
+            write_atomic_private(&self.key_path, &key)?;
+        }
+        let key = std::fs::read(&self.key_path)
+            .with_context(|| format!("Failed to read key file {}", self.key_path.display()))?;
+        if key.len() != 32 {
+            bail!("key file {} is not a 32-byte key", self.key_path.display());
+        }
+        Ok(*Key::<Aes256Gcm>::from_slice(&key))
+    }
+}
+
+fn session_path(dir: &Path, scraper: &str) -> PathBuf {
+    dir.join(format!("{}.session", scraper))
+}
+
+/// Deletes the saved session of `scraper`, or of every scraper along with
+/// the key. Returns the files removed.
+pub fn clear(config: &SessionConfig, scraper: Option<&str>) -> Result<Vec<PathBuf>> {
+    let candidates = match scraper {
+        Some(scraper) => vec![session_path(&config.dir, scraper)],
+        None if config.dir.exists() => {
+            let mut paths = Vec::new();
+            for entry in std::fs::read_dir(&config.dir)? {
+                let path = entry?.path();
+                if path.extension().is_some_and(|ext| ext == "session") {
+                    paths.push(path);
+                }
+            }
+            paths.push(config.dir.join(KEY_FILE));
+            paths
+        }
+        None => Vec::new(),
+    };
+
+    let mut removed = Vec::new();
+    for path in candidates {
+        if path.exists() {
+            std::fs::remove_file(&path)
+                .with_context(|| format!("Failed to remove {}", path.display()))?;
+            removed.push(path);
+        }
+    }
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn store(name: &str, passphrase: Option<&str>) -> SessionStore {
+        let config = SessionConfig {
+            dir: std::env::temp_dir().join(format!(
+                "rwa-session-test-{}-{}",
+                std::process::id(),
+                name
+            )),
+            ..SessionConfig::default()
+        };
+        let _ = std::fs::remove_dir_all(&config.dir);
+        SessionStore {
+            passphrase: passphrase.map(str::to_string),
+            ..SessionStore::new(&config, "jobs")
+        }
+    }
+
+    fn session() -> SavedSession {
+        SavedSession {
+            saved_at: Local::now(),
+            origin: "https://www.linkedin.com".to_string(),
+            cookies: vec![SavedCookie {
+                name: "li_at".to_string(),
+                value: "secret".to_string(),
+                domain: Some(".linkedin.com".to_string()),
+                path: Some("/".to_string()),
+                secure: true,
+                http_only: true,
+                expires: None,
+                same_site: Some("None".to_string()),
+            }],
+            local_storage: BTreeMap::from([("theme".to_string(), "dark".to_string())]),
+        }
+    }
+
+    fn cleanup(store: &SessionStore) {
+        std::fs::remove_dir_all(store.path().parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn round_trips_with_key_file_and_passphrase() {
+        for (name, passphrase) in [("key-file", None), ("passphrase", Some("hunter2"))] {
+            let store = store(name, passphrase);
+            store.save(&session()).unwrap();
+            let loaded = store.load().unwrap().unwrap();
+            assert_eq!(loaded.origin, "https://www.linkedin.com");
+            assert_eq!(loaded.cookies[0].value, "secret");
+            assert_eq!(loaded.local_storage["theme"], "dark");
+            cleanup(&store);
+        }
+    }
+
+    #[test]
+    fn same_passphrase_gives_each_file_its_own_key() {
+        let store = store("salt", Some("hunter2"));
+        let first = store.encrypt(b"same").unwrap();
+        let second = store.encrypt(b"same").unwrap();
+        let salt = |bytes: &[u8]| bytes[MAGIC.len()..MAGIC.len() + SALT_LEN].to_vec();
+        assert_ne!(salt(&first), salt(&second));
+        assert_eq!(store.decrypt(&second).unwrap(), b"same");
+    }
+
+    #[test]
+    fn rejects_wrong_key() {
+        let store = store("wrong-key", Some("hunter2"));
+        let bytes = store.encrypt(b"{}").unwrap();
+        let other = SessionStore {
+            passphrase: Some("hunter3".to_string()),
+            ..store
+        };
+        let error = other.decrypt(&bytes).unwrap_err();
+        assert_eq!(error.to_string(), "wrong key or corrupted file");
+
+        let store = self::store("wrong-key-file", None);
+        store.save(&session()).unwrap();
+        std::fs::write(&store.key_path, [7u8; 32]).unwrap();
+        assert!(store.load().is_err());
+        cleanup(&store);
+    }
+
+    #[test]
+    fn rejects_tampered_file() {
+        let store = store("tampered", Some("hunter2"));
+        let bytes = store.encrypt(b"{}").unwrap();
+        // The salt is authenticated as well as the ciphertext.
+        for index in [MAGIC.len(), bytes.len() - 1] {
+            let mut tampered = bytes.clone();
+            tampered[index] ^= 1;
+            assert_eq!(
+                store.decrypt(&tampered).unwrap_err().to_string(),
+                "wrong key or corrupted file"
+            );
+        }
+        let truncated = &bytes[..MAGIC.len() + SALT_LEN];
+        assert_eq!(
+            store.decrypt(truncated).unwrap_err().to_string(),
+            "file is truncated"
+        );
+    }
+}
@@ -0,0 +1,96 @@
+use crate::error::{Result, ScrapeError};
+use serde::Deserialize;
+use std::fmt;
+
+/// Prepended to every step script. Steps report back by returning one of
+/// `ok(code, detail)`, `skipped(code, detail)` or `failed(code, detail)`, and
+/// can poll for in-page conditions with `await waitFor(condition, timeoutMs)`.
+pub const STEP_PRELUDE: &str = r#"
+const outcome = (status, code, detail) => ({ status, code, detail: detail === undefined ? null : String(detail) });
+const ok = (code, detail) => outcome('ok', code, detail);
+const skipped = (code, detail) => outcome('skipped', code, detail);
+const failed = (code, detail) => outcome('failed', code, detail);
+const waitFor = async (condition, timeoutMs = 5000, intervalMs = 100) => {
+    const deadline = Date.now() + timeoutMs;
+    while (Date.now() < deadline) {
+        const value = condition();
+        if (value) return value;
+        await new Promise(resolve => setTimeout(resolve, intervalMs));
+    }
+    return condition();
+};
+"#;
+
+/// Failure codes with a meaning shared across scripts. Any other failure
+/// code is reported as [`ScrapeError::Script`].
+pub mod codes {
+    pub const NOT_FOUND: &str = "not_found";
+    pub const UNEXPECTED_PAGE: &str = "unexpected_page";
+    pub const AUTH_REQUIRED: &str = "auth_required";
+    pub const MANUAL_ACTION_REQUIRED: &str = "manual_action_required";
+    pub const RATE_LIMITED: &str = "rate_limited";
+}
+
+/// What an injected step script reported, deserialized from
+/// `{status, code, detail}`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum StepOutcome {
+    /// The step did what it was asked to.
+    Ok {
+        code: String,
+        detail: Option<String>,
+    },
+    /// The step had nothing to do, which is not an error.
+    Skipped {
+        code: String,
+        detail: Option<String>,
+    },
+    Failed {
+        code: String,
+        detail: Option<String>,
+    },
+}
+
+impl StepOutcome {
+    pub fn code(&self) -> &str {
+        match self {
+            StepOutcome::Ok { code, .. }
+            | StepOutcome::Skipped { code, .. }
+            | StepOutcome::Failed { code, .. } => code,
+        }
+    }
+
+    pub fn detail(&self) -> Option<&str> {
+        match self {
+            StepOutcome::Ok { detail, .. }
+            | StepOutcome::Skipped { detail, .. }
+            | StepOutcome::Failed { detail, .. } => detail.as_deref(),
+        }
+    }
+
+    /// Turns a `failed` outcome into the matching [`ScrapeError`].
+    pub fn into_result(self, step: &str) -> Result<StepOutcome> {
+        let StepOutcome::Failed { code, detail } = self else {
+            return Ok(self);
+        };
+        let detail = format!("{}: {}", step, detail.unwrap_or_default());
+        Err(match code.as_str() {
+            codes::NOT_FOUND => ScrapeError::SelectorMissing(detail),
+            codes::UNEXPECTED_PAGE => ScrapeError::UnexpectedPage(detail),
+            codes::AUTH_REQUIRED => ScrapeError::AuthRequired(detail),
+            codes::MANUAL_ACTION_REQUIRED => ScrapeError::ManualActionRequired(detail),
+            codes::RATE_LIMITED => ScrapeError::RateLimited(detail),
+            _ => ScrapeError::Script(format!("{} ({})", detail, code)),
+        })
+    }
+}
+
+impl fmt::Display for StepOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.detail() {
+            Some(detail) => write!(f, "{} ({})", self.code(), detail),
+            None => f.write_str(self.code()),
+        }
+    }
+}
@@ -0,0 +1,216 @@
+use crate::common::BrowserSession;
+use crate::debug_println;
+use crate::error::{Result, ScrapeError};
+use crate::selectors::SelectorChain;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+/// Default timeout and polling interval for [`Wait`], from `[waits]`.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(default)]
+pub struct WaitConfig {
+    pub timeout_ms: u64,
+    pub poll_interval_ms: u64,
+}
+
+impl Default for WaitConfig {
+    fn default() -> Self {
+        Self {
+            timeout_ms: 15_000,
+            poll_interval_ms: 250,
+        }
+    }
+}
+
+/// Polls the page until a condition holds, failing with
+/// [`ScrapeError::NavigationTimeout`] once the timeout passes.
+pub struct Wait<'a> {
+    session: &'a BrowserSession,
+    timeout: Duration,
+    interval: Duration,
+}
+
+impl<'a> Wait<'a> {
+    pub fn new(session: &'a BrowserSession, config: WaitConfig) -> Self {
+        Self {
+            session,
+            timeout: Duration::from_millis(config.timeout_ms),
+            interval: Duration::from_millis(config.poll_interval_ms),
+        }
+    }
+
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Polls `predicate` until it returns `true`.
+    pub async fn until<F, Fut>(&self, description: &str, mut predicate: F) -> Result<()>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<bool>>,
+    {
+        let deadline = Instant::now() + self.timeout;
+        loop {
+            if predicate().await? {
+                debug_println!("Wait satisfied: {}", description);
+                return Ok(());
+            }
+            if Instant::now() >= deadline {
+                return Err(self.timed_out(description));
+            }
+            tokio::time::sleep(self.interval).await;
+        }
+    }
+
+    fn timed_out(&self, description: &str) -> ScrapeError {
+        ScrapeError::NavigationTimeout(format!("waited {:?} for {}", self.timeout, description))
+    }
+
+    /// Polls a JS predicate; `args` are exposed to it as `arguments`.
+    pub async fn until_script(
+        &self,
+        description: &str,
+        script: &str,
+        args: Vec<Value>,
+    ) -> Result<()> {
+        let session = self.session;
+        let args = &args;
+        self.until(description, move || async move {
+            session
+                .execute_with_args::<Option<bool>>(script, args.clone())
+                .await
+                .map(|result| result.unwrap_or(false))
+        })
+        .await
+    }
+
+    /// Waits for the element `chain` picks to be visible.
+    pub async fn for_visible(&self, chain: &SelectorChain) -> Result<()> {
+        let script = format!("{}\nreturn isVisible(pick(arguments[0]));", VISIBILITY_JS);
+        self.until_script(
+            &format!("{} to be visible", chain),
+            &script,
+            vec![json!(chain)],
+        )
+        .await
+    }
+
+    /// Waits for an element that is visible and not disabled.
+    pub async fn for_clickable(&self, chain: &SelectorChain) -> Result<()> {
+        let script = format!(
+            "{}\nconst el = pick(arguments[0]);\nreturn isVisible(el) && !el.disabled;",
+            VISIBILITY_JS
+        );
+        self.until_script(
+            &format!("{} to be clickable", chain),
+            &script,
+            vec![json!(chain)],
+        )
+        .await
+    }
+
+    /// Waits until the current URL differs from `from`, returning the new URL.
+    pub async fn for_url_change(&self, from: &str) -> Result<String> {
+        let client = self.session.client();
+        self.until(
+            &format!("URL to change from {}", from),
+            move || async move { Ok(client.current_url().await?.as_str() != from) },
+        )
+        .await?;
+        Ok(client.current_url().await?.to_string())
+    }
+
+    /// Waits until the number of elements `chain` matches stops changing
+    /// for `stable_for`, returning the final count.
+    pub async fn for_count_stable(
+        &self,
+        chain: &SelectorChain,
+        stable_for: Duration,
+    ) -> Result<usize> {
+        let script = "return pickAll(arguments[0]).length;";
+        let deadline = Instant::now() + self.timeout;
+        let mut last: Option<(usize, Instant)> = None;
+        loop {
+            let count: usize = self
+                .session
+                .execute_with_args(script, vec![json!(chain)])
+                .await?;
+            match last {
+                Some((previous, since)) if previous == count => {
+                    if since.elapsed() >= stable_for {
+                        return Ok(count);
+                    }
+                }
+                _ => last = Some((count, Instant::now())),
+            }
+            if Instant::now() >= deadline {
+                return Err(self.timed_out(&format!("count of {} to settle", chain)));
+            }
+            tokio::time::sleep(self.interval).await;
+        }
+    }
+
+    /// Waits until no fetch/XHR is in flight and no resource has finished
+    /// loading for `idle_for`.
+    pub async fn for_network_idle(&self, idle_for: Duration) -> Result<()> {
+        let script = format!("{}\nreturn networkIdleFor(arguments[0]);", NETWORK_IDLE_JS);
+        self.until_script(
+            "network to go idle",
+            &script,
+            vec![(idle_for.as_millis() as u64).into()],
+        )
+        .await
+    }
+}
+
+const VISIBILITY_JS: &str = r#"
+function isVisible(el) {
+    if (!el) return false;
+    const style = window.getComputedStyle(el);
+    if (style.visibility === 'hidden' || style.display === 'none' || style.opacity === '0') return false;
+    const rect = el.getBoundingClientRect();
+    return rect.width > 0 && rect.height > 0;
+}
+"#;
+
+/// Installs (once per page) counters around fetch and XHR, and combines them
+/// with the Resource Timing entries for requests started before installation.
+const NETWORK_IDLE_JS: &str = r#"
+function networkIdleFor(idleMs) {
+    if (!window.__scraperNetwork) {
+        const state = { pending: 0, lastActivity: performance.now() };
+        const done = () => { state.pending--; state.lastActivity = performance.now(); };
+        const originalFetch = window.fetch;
+        if (originalFetch) {
+            window.fetch = function (...args) {
+                state.pending++;
+                state.lastActivity = performance.now();
+                return originalFetch.apply(this, args).finally(done);
+            };
+        }
+        const originalSend = XMLHttpRequest.prototype.send;
+        XMLHttpRequest.prototype.send = function (...args) {
+            state.pending++;
+            state.lastActivity = performance.now();
+            this.addEventListener('loadend', done, { once: true });
+            return originalSend.apply(this, args);
+        };
+        window.__scraperNetwork = state;
+    }
+    const state = window.__scraperNetwork;
+    const lastResource = performance.getEntriesByType('resource')
+        .reduce((latest, entry) => Math.max(latest, entry.responseEnd), 0);
+    const lastActivity = Math.max(state.lastActivity, lastResource);
+    return document.readyState === 'complete'
+        && state.pending <= 0
+        && performance.now() - lastActivity >= idleMs;
+}
+"#;